@@ -0,0 +1,15 @@
+use swc_ecma_ast::TsTypeAliasDecl;
+
+use crate::interface::generics;
+use crate::ir::{Doc, TypeDef, TypeDefKind};
+use crate::utils::{lower_type, Context};
+
+pub fn handle_alias(alias: TsTypeAliasDecl, cx: &mut Context) -> TypeDef {
+    TypeDef {
+        name: alias.id.sym.to_string(),
+        generics: generics(&alias.type_params),
+        kind: TypeDefKind::Alias(lower_type(*alias.type_ann, cx)),
+        doc: Doc::default(),
+        span: alias.span,
+    }
+}
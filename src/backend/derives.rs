@@ -0,0 +1,244 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::rust::MapType;
+use crate::ir::{Module, NumberType, Primitive, TypeDef, TypeDefKind, TypeExpr};
+
+// Standard traits that can be inferred, in the order they are derived
+pub const DERIVABLE: [&str; 7] = [
+    "Clone",
+    "PartialEq",
+    "Eq",
+    "Hash",
+    "PartialOrd",
+    "Ord",
+    "Default",
+];
+
+type Traits = BTreeSet<&'static str>;
+
+fn all() -> Traits {
+    DERIVABLE.into_iter().collect()
+}
+
+// Traits implemented by `serde_json::Value` and `serde_json::Map`
+fn json() -> Traits {
+    ["Clone", "PartialEq", "Eq", "Default"]
+        .into_iter()
+        .collect()
+}
+
+struct Inference<'a> {
+    types: HashMap<&'a str, &'a TypeDef>,
+    map: MapType,
+    // Traits that can be derived for each declared type, narrowed until a fixpoint
+    traits: HashMap<&'a str, Traits>,
+}
+
+impl<'a> Inference<'a> {
+    // Traits implemented by the Rust type rendered for `ty`, where `generics` are the
+    // generic parameters in scope. Deriving on a generic type bounds its parameters by
+    // the derived traits, so they are assumed to implement all of them.
+    fn expr(&self, ty: &TypeExpr, generics: &[String]) -> Traits {
+        match ty {
+            // Floats are only partially ordered and can't be hashed
+            TypeExpr::Primitive(Primitive::Number(Some(NumberType::F64))) => {
+                ["Clone", "PartialEq", "PartialOrd", "Default"]
+                    .into_iter()
+                    .collect()
+            }
+            TypeExpr::Primitive(Primitive::String | Primitive::Number(_) | Primitive::Boolean) => {
+                all()
+            }
+            TypeExpr::Primitive(Primitive::Any | Primitive::Object) | TypeExpr::Union(_) => json(),
+            TypeExpr::Array(ty) | TypeExpr::Optional(ty) => {
+                let mut traits = self.expr(ty, generics);
+                traits.insert("Default");
+                traits
+            }
+            TypeExpr::Map(key, value) => {
+                let mut traits: Traits = match self.map {
+                    MapType::HashMap => ["Clone", "PartialEq", "Eq"].into_iter().collect(),
+                    MapType::BTreeMap => all(),
+                };
+                traits.retain(|t| self.expr(key, generics).contains(t));
+                traits.retain(|t| self.expr(value, generics).contains(t));
+                traits.insert("Default");
+                traits
+            }
+            // Types written as is by the configuration can't be relied on
+            TypeExpr::Custom { rust: Some(_), .. } => Traits::new(),
+            TypeExpr::Custom { ty, .. } => self.expr(ty, generics),
+            TypeExpr::Tuple(elems) => {
+                let mut traits = all();
+                for elem in elems {
+                    let elem = self.expr(elem, generics);
+                    traits.retain(|t| elem.contains(t));
+                }
+                traits
+            }
+            TypeExpr::Ref { name, args } => {
+                let mut traits = if generics.contains(name) || name == "BigNumber" {
+                    all()
+                } else {
+                    // Types that are not declared in the module can't be relied on
+                    self.traits.get(name.as_str()).cloned().unwrap_or_default()
+                };
+                for arg in args {
+                    let arg = self.expr(arg, generics);
+                    traits.retain(|t| arg.contains(t));
+                }
+                traits
+            }
+        }
+    }
+
+    fn def(&self, def: &TypeDef) -> Traits {
+        match &def.kind {
+            TypeDefKind::Struct(fields) => {
+                let mut traits = all();
+                for field in fields {
+                    let field = self.expr(&field.ty, &def.generics);
+                    traits.retain(|t| field.contains(t));
+                }
+                traits
+            }
+            TypeDefKind::Map { key, value } => self.expr(
+                &TypeExpr::Map(Box::new(key.clone()), Box::new(value.clone())),
+                &def.generics,
+            ),
+            TypeDefKind::Enum(members) => {
+                let mut traits = all();
+                if members.is_empty() {
+                    traits.remove("Default");
+                }
+                traits
+            }
+            TypeDefKind::Alias(ty) => self.expr(ty, &def.generics),
+        }
+    }
+}
+
+// Infer the standard traits that can be derived for each type declared in the
+// module. Every type starts with all of them, and the sets are narrowed by the
+// types of the fields until nothing changes, which handles recursive types.
+pub fn infer_derives(module: &Module, map: MapType) -> HashMap<String, Vec<&'static str>> {
+    let types: HashMap<&str, &TypeDef> =
+        module.types().map(|def| (def.name.as_str(), def)).collect();
+    let mut inference = Inference {
+        traits: types.keys().map(|name| (*name, all())).collect(),
+        types,
+        map,
+    };
+
+    loop {
+        let narrowed: HashMap<&str, Traits> = inference
+            .types
+            .iter()
+            .map(|(name, def)| (*name, inference.def(def)))
+            .collect();
+        if narrowed == inference.traits {
+            break;
+        }
+        inference.traits = narrowed;
+    }
+
+    inference
+        .traits
+        .into_iter()
+        .map(|(name, traits)| {
+            let derives = DERIVABLE
+                .into_iter()
+                .filter(|derive| traits.contains(derive))
+                .collect();
+            (name.to_string(), derives)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lower_text;
+
+    fn derives(text: &str, map: MapType) -> HashMap<String, Vec<&'static str>> {
+        let (mut module, overrides) = lower_text(text);
+        overrides.apply(&mut module);
+        infer_derives(&module, map)
+    }
+
+    #[test]
+    fn recursive_type() {
+        let text = "export interface Tree {\n  value: string\n  children: Tree[]\n}\n";
+        let derives = derives(text, MapType::HashMap);
+        assert_eq!(derives["Tree"], DERIVABLE);
+    }
+
+    #[test]
+    fn mutually_recursive_types() {
+        let text = "export interface Vault {\n  id: string\n  loans: Loan[]\n}\n\
+                    export interface Loan {\n  vault?: Vault\n  amount: number\n}\n";
+        let derives = derives(text, MapType::HashMap);
+        assert_eq!(derives["Vault"], DERIVABLE);
+        assert_eq!(derives["Loan"], DERIVABLE);
+    }
+
+    #[test]
+    fn mutually_recursive_types_with_a_float() {
+        // The float of `Loan` narrows `Vault` through the cycle, and `Loan` through
+        // `Vault` in turn
+        let text = "export interface Vault {\n  id: string\n  loans: Loan[]\n}\n\
+                    export interface Loan {\n  vault?: Vault\n  /** @number f64 */\n  \
+                    amount: number\n}\n";
+        let derives = derives(text, MapType::HashMap);
+        let partial = ["Clone", "PartialEq", "PartialOrd", "Default"];
+        assert_eq!(derives["Vault"], partial);
+        assert_eq!(derives["Loan"], partial);
+    }
+
+    #[test]
+    fn floats() {
+        let text = "export interface Price {\n  /** @number f64 */\n  amount: number\n}\n";
+        let derives = derives(text, MapType::BTreeMap);
+        assert_eq!(
+            derives["Price"],
+            ["Clone", "PartialEq", "PartialOrd", "Default"]
+        );
+    }
+
+    #[test]
+    fn maps() {
+        let text = "export interface Balances {\n  tokens: Record<string, number>\n}\n";
+        // `HashMap` can be neither hashed nor ordered, `BTreeMap` can
+        let hash_map = derives(text, MapType::HashMap);
+        assert_eq!(
+            hash_map["Balances"],
+            ["Clone", "PartialEq", "Eq", "Default"]
+        );
+        let btree_map = derives(text, MapType::BTreeMap);
+        assert_eq!(btree_map["Balances"], DERIVABLE);
+    }
+
+    #[test]
+    fn generics() {
+        // Parameters are assumed to implement every trait, their arguments narrow the
+        // traits of the references
+        let text = "export interface Page<T> {\n  items: T[]\n  total: number\n}\n\
+                    export interface Prices {\n  /** @number f64 */\n  value: number\n}\n\
+                    export interface Ids {\n  page: Page<string>\n}\n\
+                    export interface Quotes {\n  page: Page<Prices>\n}\n";
+        let derives = derives(text, MapType::HashMap);
+        assert_eq!(derives["Page"], DERIVABLE);
+        assert_eq!(derives["Ids"], DERIVABLE);
+        assert_eq!(
+            derives["Quotes"],
+            ["Clone", "PartialEq", "PartialOrd", "Default"]
+        );
+    }
+
+    #[test]
+    fn undeclared_types() {
+        let text = "export interface Block {\n  hash: string\n  time: Date\n}\n";
+        let derives = derives(text, MapType::HashMap);
+        assert!(derives["Block"].is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+mod derives;
+mod protobuf;
+mod rust;
+
+pub use protobuf::{is_proto_scalar, DecimalRepr, ProtoOptions, ProtobufBackend};
+pub use rust::{field_ident, rust_ident, MapType, RustBackend, RustOptions, Visibility};
+
+use crate::ir::{Field, Item, Method, Module, Service, TypeDef, TypeDefKind, TypeExpr, Variant};
+
+// Output format of the conversion. Each backend renders the type references and
+// every construct of the intermediate representation.
+//
+// The conversions and the bridge are not backends: rather than rendering each
+// declaration in a format of their own, they join the types rendered by the Rust and
+// proto backends, and are generated from a module with both of them.
+pub trait Backend {
+    // Reference to a type, e.g. in a field or a return type
+    fn render_type(&self, ty: &TypeExpr) -> String;
+
+    fn render_field(&self, field: &Field) -> String;
+    fn render_struct(&self, def: &TypeDef, fields: &[Field]) -> String;
+    fn render_map(&self, def: &TypeDef, key: &TypeExpr, value: &TypeExpr) -> String;
+    fn render_enum(&self, def: &TypeDef, members: &[Variant]) -> String;
+    fn render_alias(&self, def: &TypeDef, ty: &TypeExpr) -> String;
+    fn render_method(&self, method: &Method) -> String;
+
+    // Methods of a class, rendered one after the other by default
+    fn render_service(&self, service: &Service) -> String {
+        service
+            .methods
+            .iter()
+            .map(|method| self.render_method(method))
+            .collect()
+    }
+
+    fn render_item(&self, item: &Item) -> String {
+        match item {
+            Item::Type(def) => match &def.kind {
+                TypeDefKind::Struct(fields) => self.render_struct(def, fields),
+                TypeDefKind::Map { key, value } => self.render_map(def, key, value),
+                TypeDefKind::Enum(members) => self.render_enum(def, members),
+                TypeDefKind::Alias(ty) => self.render_alias(def, ty),
+            },
+            Item::Service(service) => self.render_service(service),
+        }
+    }
+
+    // Header of the file generated from `source`, given the module and the rendered body
+    fn header(&self, _source: &str, _module: &Module, _body: &str) -> String {
+        String::new()
+    }
+
+    // Footer of the file generated for `module`, after the rendered body
+    fn footer(&self, _module: &Module) -> String {
+        String::new()
+    }
+}
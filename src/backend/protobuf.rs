@@ -0,0 +1,417 @@
+use convert_case::{Case, Casing};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+
+use super::Backend;
+use crate::ir::{Field, Method, Module, Primitive, Service, TypeDef, TypeExpr, Variant};
+
+// Well-known types that can be emitted in proto, with the file to import for each of them.
+const PROTO_WELL_KNOWN_TYPES: [(&str, &str); 4] = [
+    ("google.protobuf.Struct", "google/protobuf/struct.proto"),
+    ("google.protobuf.ListValue", "google/protobuf/struct.proto"),
+    ("google.protobuf.Value", "google/protobuf/struct.proto"),
+    (
+        "google.protobuf.Timestamp",
+        "google/protobuf/timestamp.proto",
+    ),
+];
+
+const PROTO_DECIMAL_MESSAGE: &str = "// Decimal number equal to `units + nanos * 10^-9`.
+message Decimal {
+  int64 units = 1;
+  int32 nanos = 2;
+}
+";
+
+/// Representation of `BigNumber` in proto
+#[derive(Clone, Copy, Debug)]
+pub enum DecimalRepr {
+    /// Decimal string such as `"1.5"`
+    String,
+    /// Generated `Decimal` message
+    Message,
+}
+
+#[derive(Clone)]
+pub struct ProtoOptions {
+    pub decimal: DecimalRepr,
+    // Package of the module being converted
+    pub package: String,
+    // Types declared in the other converted modules, mapped to their package
+    pub external_types: Rc<HashMap<String, String>>,
+    // Enums declared in the converted modules, which can't be used as rpc messages
+    pub enums: Rc<HashSet<String>>,
+}
+
+// Keywords starting a declaration in proto, which are escaped in field names
+const PROTO_KEYWORDS: [&str; 17] = [
+    "syntax",
+    "import",
+    "package",
+    "option",
+    "message",
+    "enum",
+    "service",
+    "rpc",
+    "reserved",
+    "extensions",
+    "extend",
+    "oneof",
+    "map",
+    "repeated",
+    "optional",
+    "required",
+    "group",
+];
+
+// Lines of a `//` comment made of `text`, indented by `indent`
+fn comment(text: &str, indent: &str) -> String {
+    text.lines()
+        .map(|line| match line.trim_end() {
+            "" => format!("{}//\n", indent),
+            line => format!("{}// {}\n", indent, line),
+        })
+        .collect()
+}
+
+// Description of a method followed by its return value, its parameters are
+// documented on the fields of its request
+fn method_comment(method: &Method) -> String {
+    match &method.doc.returns {
+        Some(returns) if method.doc.text.is_empty() => format!("Returns: {}", returns),
+        Some(returns) => format!("{}\n\nReturns: {}", method.doc.text, returns),
+        None => method.doc.text.clone(),
+    }
+}
+
+pub fn is_proto_scalar(val: &str) -> bool {
+    matches!(
+        val,
+        "double"
+            | "float"
+            | "int32"
+            | "int64"
+            | "uint32"
+            | "uint64"
+            | "sint32"
+            | "sint64"
+            | "fixed32"
+            | "fixed64"
+            | "sfixed32"
+            | "sfixed64"
+            | "bool"
+            | "string"
+            | "bytes"
+    )
+}
+
+// Proto3 messages, enums and gRPC services
+pub struct ProtobufBackend {
+    options: ProtoOptions,
+}
+
+impl ProtobufBackend {
+    pub fn new(options: ProtoOptions) -> ProtobufBackend {
+        ProtobufBackend { options }
+    }
+
+    fn type_ref(&self, name: &str) -> String {
+        match name {
+            "BigNumber" => match self.options.decimal {
+                DecimalRepr::String => String::from("string"),
+                DecimalRepr::Message => String::from("Decimal"),
+            },
+            "Date" => String::from("google.protobuf.Timestamp"),
+            _ => match self.options.external_types.get(name) {
+                Some(package) => format!("{}.{}", package, name),
+                None => name.to_string(),
+            },
+        }
+    }
+
+    // Label and type of a field: arrays are `repeated`, maps are `map` fields and
+    // optional fields keep track of presence so that they can be converted back
+    // to an `Option` losslessly.
+    pub fn field_type(&self, ty: &TypeExpr) -> (&'static str, String) {
+        match ty {
+            TypeExpr::Array(elem) => ("repeated", self.render_type(elem)),
+            TypeExpr::Map(key, value) => (
+                "",
+                format!(
+                    "map<{}, {}>",
+                    self.render_type(key),
+                    self.render_type(value)
+                ),
+            ),
+            TypeExpr::Optional(ty) => match ty.as_ref() {
+                TypeExpr::Array(_) | TypeExpr::Map(..) => self.field_type(ty),
+                ty => ("optional", self.render_type(ty)),
+            },
+            TypeExpr::Custom {
+                ty, proto: None, ..
+            } => self.field_type(ty),
+            ty => ("", self.render_type(ty)),
+        }
+    }
+
+    // Whether values of `ty` are messages that can be used as is in an rpc
+    pub fn is_message(&self, ty: &TypeExpr) -> bool {
+        if let TypeExpr::Custom {
+            ty, proto: None, ..
+        } = ty
+        {
+            return self.is_message(ty);
+        }
+        if let TypeExpr::Ref { name, .. } = ty {
+            if self.options.enums.contains(name) {
+                return false;
+            }
+        }
+        !matches!(
+            ty,
+            TypeExpr::Array(_) | TypeExpr::Map(..) | TypeExpr::Optional(_)
+        ) && !is_proto_scalar(&self.render_type(ty))
+    }
+
+    // Name of a field, with an underscore suffix for keywords
+    pub fn field_name(field: &Field) -> String {
+        let name = field.code_name().to_case(Case::Snake);
+        if PROTO_KEYWORDS.contains(&name.as_str()) {
+            format!("{}_", name)
+        } else {
+            name
+        }
+    }
+
+    fn message(&self, name: &str, fields: &[Field]) -> String {
+        let mut out = format!("message {} {{\n", name);
+        // Fields without a number of their own take the next one that is free
+        let taken: Vec<u32> = fields.iter().filter_map(|field| field.tag).collect();
+        let mut next = 1;
+        for field in fields {
+            let number = field.tag.unwrap_or_else(|| {
+                while taken.contains(&next) {
+                    next += 1;
+                }
+                next += 1;
+                next - 1
+            });
+            let mut options = Vec::new();
+            // Escaped and renamed fields keep their key in the JSON mapping
+            if Self::field_name(field) != field.name.to_case(Case::Snake) {
+                options.push(format!("json_name = \"{}\"", field.name));
+            }
+            if field.doc.deprecated.is_some() {
+                options.push(String::from("deprecated = true"));
+            }
+            let options = if options.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", options.join(", "))
+            };
+            out.push_str(&comment(&field.doc.text, "  "));
+            out.push_str(&format!(
+                "  {} = {}{};\n",
+                self.render_field(field),
+                number,
+                options
+            ));
+        }
+        out.push_str("}\n\n");
+        out
+    }
+
+    // Words of the rendered `types`, as in the fields of a message, such as
+    // `google.protobuf.Value` or `loan.LoanScheme`
+    fn words(&self, types: &[TypeExpr]) -> BTreeSet<String> {
+        types
+            .iter()
+            .map(|ty| self.field_type(ty).1)
+            .flat_map(|ty| {
+                ty.split(|c: char| !(c.is_alphanumeric() || c == '.' || c == '_'))
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    // Files imported for `types`: those of the well-known types and of the other
+    // modules whose types they reference
+    pub fn imports(&self, types: &[TypeExpr]) -> BTreeSet<String> {
+        let words = self.words(types);
+        let mut imports: BTreeSet<String> = PROTO_WELL_KNOWN_TYPES
+            .iter()
+            .filter(|(name, _)| words.contains(*name))
+            .map(|(_, file)| file.to_string())
+            .collect();
+        for package in self.options.external_types.values() {
+            let prefix = format!("{}.", package);
+            if words.iter().any(|word| word.starts_with(&prefix)) {
+                imports.insert(format!("{}.proto", package));
+            }
+        }
+        imports
+    }
+
+    // Synthesize the `<Method>Request` message and, when the return type is not
+    // already a message, the `<Method>Response` wrapper.
+    // Returns the messages along with the rpc declaration to be printed inside the service.
+    fn render_rpc(&self, method: &Method) -> (String, String) {
+        let rpc_name = method.name.to_case(Case::Pascal);
+
+        let request = format!("{}Request", rpc_name);
+        let mut out = self.message(&request, &method.params);
+
+        let response = if self.is_message(&method.returns) {
+            self.render_type(&method.returns)
+        } else {
+            let response = format!("{}Response", rpc_name);
+            out.push_str(&self.message(
+                &response,
+                &[Field::new(String::from("result"), method.returns.clone())],
+            ));
+            response
+        };
+
+        let rpc = match method.doc.deprecated {
+            Some(_) => format!(
+                "rpc {}({}) returns ({}) {{\n    option deprecated = true;\n  }}",
+                rpc_name, request, response
+            ),
+            None => format!("rpc {}({}) returns ({});", rpc_name, request, response),
+        };
+        (out, rpc)
+    }
+}
+
+impl Backend for ProtobufBackend {
+    // Singular type of a value. Collections that can't be nested in proto fall back
+    // to the `google.protobuf` dynamic types.
+    fn render_type(&self, ty: &TypeExpr) -> String {
+        match ty {
+            TypeExpr::Primitive(primitive) => String::from(match primitive {
+                Primitive::String => "string",
+                Primitive::Number(ty) => ty.unwrap_or_default().proto(),
+                Primitive::Boolean => "bool",
+                Primitive::Any => "google.protobuf.Value",
+                Primitive::Object => "google.protobuf.Struct",
+            }),
+            TypeExpr::Array(_) | TypeExpr::Tuple(_) => String::from("google.protobuf.ListValue"),
+            TypeExpr::Map(..) => String::from("google.protobuf.Struct"),
+            TypeExpr::Ref { name, .. } => self.type_ref(name),
+            TypeExpr::Union(_) => String::from("google.protobuf.Value"),
+            TypeExpr::Optional(ty) => self.render_type(ty),
+            TypeExpr::Custom {
+                proto: Some(proto), ..
+            } => proto.clone(),
+            TypeExpr::Custom { ty, .. } => self.render_type(ty),
+        }
+    }
+
+    fn render_field(&self, field: &Field) -> String {
+        let (label, ty) = self.field_type(&field.ty);
+        let name = Self::field_name(field);
+        if label.is_empty() {
+            format!("{} {}", ty, name)
+        } else {
+            format!("{} {} {}", label, ty, name)
+        }
+    }
+
+    fn render_struct(&self, def: &TypeDef, fields: &[Field]) -> String {
+        let name = if !def.generics.is_empty() {
+            format!("{}<{}>", def.name, def.generics.join(" "))
+        } else {
+            def.name.clone()
+        };
+        comment(&def.doc.text, "") + &self.message(&name, fields)
+    }
+
+    // Maps can't be top-level messages, they are wrapped in a single field.
+    fn render_map(&self, def: &TypeDef, key: &TypeExpr, value: &TypeExpr) -> String {
+        comment(&def.doc.text, "")
+            + &self.message(
+                &def.name,
+                &[Field::new(
+                    String::from("entries"),
+                    TypeExpr::Map(Box::new(key.clone()), Box::new(value.clone())),
+                )],
+            )
+    }
+
+    // Values are prefixed with the enum name as they share the scope of the
+    // enclosing package, and the first one is the default.
+    fn render_enum(&self, def: &TypeDef, members: &[Variant]) -> String {
+        let prefix = def.name.to_case(Case::UpperSnake);
+        let mut out = comment(&def.doc.text, "");
+        out.push_str(&format!("enum {} {{\n", def.name));
+        for (number, member) in (0..).zip(members) {
+            out.push_str(&comment(&member.doc.text, "  "));
+            let options = match member.doc.deprecated {
+                Some(_) => " [deprecated = true]",
+                None => "",
+            };
+            out.push_str(&format!(
+                "  {}_{} = {}{};\n",
+                prefix,
+                member.name.to_case(Case::UpperSnake),
+                number,
+                options
+            ));
+        }
+        out.push_str("}\n\n");
+        out
+    }
+
+    // Proto has no aliases, the aliased type is wrapped in a message instead.
+    fn render_alias(&self, def: &TypeDef, ty: &TypeExpr) -> String {
+        comment(&def.doc.text, "")
+            + &self.message(&def.name, &[Field::new(String::from("value"), ty.clone())])
+    }
+
+    fn render_method(&self, method: &Method) -> String {
+        self.render_rpc(method).0
+    }
+
+    fn render_service(&self, service: &Service) -> String {
+        let mut out = String::new();
+        let mut rpcs = String::new();
+        for method in &service.methods {
+            let (messages, rpc) = self.render_rpc(method);
+            out.push_str(&messages);
+            rpcs.push_str(&comment(&method_comment(method), "  "));
+            rpcs.push_str(&format!("  {}\n", rpc));
+        }
+
+        out.push_str(&format!("service {} {{\n", service.name));
+        out.push_str(&rpcs);
+        out.push_str("}\n\n");
+        out
+    }
+
+    // Header of a generated proto file: the syntax, the package, the imports of the
+    // well-known types and of the other modules referenced by its types, and the
+    // `Decimal` message if it is used.
+    fn header(&self, _source: &str, module: &Module, _body: &str) -> String {
+        let options = &self.options;
+        let types: Vec<TypeExpr> = module.field_types().into_iter().map(|(_, ty)| ty).collect();
+
+        let mut header = format!("syntax = \"proto3\";\n\npackage {};\n\n", options.package);
+
+        let imports = self.imports(&types);
+        for import in &imports {
+            header.push_str(&format!("import \"{}\";\n", import));
+        }
+        if !imports.is_empty() {
+            header.push('\n');
+        }
+
+        if let DecimalRepr::Message = options.decimal {
+            if self.words(&types).contains("Decimal") {
+                header.push_str(PROTO_DECIMAL_MESSAGE);
+                header.push('\n');
+            }
+        }
+        header
+    }
+}
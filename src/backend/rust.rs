@@ -0,0 +1,756 @@
+use convert_case::{Case, Casing};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+
+use super::derives::infer_derives;
+use super::Backend;
+use crate::ir::{
+    Doc, Field, Literal, Method, Module, NumberType, Primitive, TypeDef, TypeDefKind, TypeExpr,
+    Variant,
+};
+
+const RUST_KEYWORDS: [&str; 52] = [
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield", "try", "gen",
+];
+
+// Identifier usable in Rust for `name`. Keywords are written as raw identifiers, except
+// the ones that can't be, which get an underscore suffix, as prost does.
+pub fn rust_ident(name: &str) -> String {
+    match name {
+        "self" | "Self" | "super" | "crate" => format!("{}_", name),
+        name if RUST_KEYWORDS.contains(&name) => format!("r#{}", name),
+        name => name.to_string(),
+    }
+}
+
+// Identifier of the Rust field of `field`
+pub fn field_ident(field: &Field) -> String {
+    rust_ident(&field.code_name().to_case(Case::Snake))
+}
+
+// Whether the key of `field` needs a `#[serde(rename)]`: keys that don't survive the
+// round-trip through snake case, or whose field is renamed or escaped with a suffix
+fn needs_rename(field: &Field) -> bool {
+    let snake = field.code_name().to_case(Case::Snake);
+    let ident = rust_ident(&snake);
+    snake.to_case(Case::Camel) != field.name || (ident != snake && !ident.starts_with("r#"))
+}
+
+// Lines of a `///` doc comment made of `text`, indented by `indent`. Code blocks
+// without a language are marked as text, as rustdoc would run them as Rust doctests.
+fn doc_comment(text: &str, indent: &str) -> String {
+    let mut out = String::new();
+    let mut code = false;
+    for line in text.lines() {
+        let line = match line.trim_start().strip_prefix("```") {
+            Some(lang) => {
+                code = !code;
+                if code && lang.is_empty() {
+                    String::from("```text")
+                } else {
+                    line.trim_start().to_string()
+                }
+            }
+            // Lines indented as much would be code blocks as well
+            None if !code && line.starts_with("    ") => line.trim_start().to_string(),
+            None => line.to_string(),
+        };
+        if line.is_empty() {
+            out.push_str(&format!("{}///\n", indent));
+        } else {
+            out.push_str(&format!("{}/// {}\n", indent, line));
+        }
+    }
+    out
+}
+
+// `#[deprecated]` attribute of a member deprecated by its JSDoc, indented by `indent`
+fn deprecated_attribute(doc: &Doc, indent: &str) -> String {
+    match doc.deprecated.as_deref() {
+        None => String::new(),
+        Some("") => format!("{}#[deprecated]\n", indent),
+        Some(note) => format!(
+            "{}#[deprecated(note = {:?})]\n",
+            indent,
+            note.replace('\n', " ")
+        ),
+    }
+}
+
+// Description of a method followed by the sections of its parameters and return value
+fn method_doc(method: &Method) -> String {
+    let mut sections = vec![method.doc.text.clone()];
+    let params: Vec<String> = method
+        .params
+        .iter()
+        .filter(|param| !param.doc.text.is_empty())
+        .map(|param| {
+            format!(
+                "* `{}` - {}",
+                rust_ident(&param.name.to_case(Case::Snake)),
+                param.doc.text.replace('\n', "\n  ")
+            )
+        })
+        .collect();
+    if !params.is_empty() {
+        sections.push(format!("# Arguments\n\n{}", params.join("\n")));
+    }
+    if let Some(returns) = &method.doc.returns {
+        sections.push(format!("# Returns\n\n{}", returns));
+    }
+    sections.retain(|section| !section.is_empty());
+    sections.join("\n\n")
+}
+
+// Tests checking that the JSON examples of each type, and of the parameters and
+// result of each method, survive a round-trip through the generated type. The
+// round-trip functions are emitted only when a test calls them, as they would be
+// dead code otherwise.
+const EXAMPLES_HEADER: &str = "#[cfg(test)]
+mod examples {
+    use super::*;
+    use serde_json::Value;
+";
+
+const ROUND_TRIP: &str = "
+    // Deserialize `json`, then check that the value serializes back to the example
+    fn round_trip<T: serde::de::DeserializeOwned + serde::Serialize>(json: &str) {
+        let example: Value = serde_json::from_str(json).expect(\"example isn't JSON\");
+        check::<T>(example);
+    }
+";
+
+const ROUND_TRIP_PARAMS: &str = "
+    // Same as `round_trip` for the tuple of the `params` parameters of a method. The
+    // trailing optional parameters can be left out of the example, as they are when
+    // calling the method.
+    fn round_trip_params<T: serde::de::DeserializeOwned + serde::Serialize>(
+        json: &str,
+        params: usize,
+    ) {
+        let mut example: Value = serde_json::from_str(json).expect(\"example isn't JSON\");
+        if let Value::Array(args) = &mut example {
+            while args.len() < params {
+                args.push(Value::Null);
+            }
+        }
+        check::<T>(example);
+    }
+";
+
+const EXAMPLES_CHECK: &str = "
+    fn check<T: serde::de::DeserializeOwned + serde::Serialize>(example: Value) {
+        let value: T =
+            serde_json::from_value(example.clone()).expect(\"example doesn't deserialize\");
+        let serialized = serde_json::to_value(&value).unwrap();
+        assert!(
+            same(&serialized, &example),
+            \"{} was serialized as {}\",
+            example,
+            serialized
+        );
+    }
+
+    // Whether two JSON values are the same, where `null` stands for a missing key and
+    // numbers can be written as strings, as decimals are
+    fn same(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Object(a), Value::Object(b)) => a.keys().chain(b.keys()).all(|key| {
+                same(
+                    a.get(key).unwrap_or(&Value::Null),
+                    b.get(key).unwrap_or(&Value::Null),
+                )
+            }),
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+            }
+            (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+            (Value::Number(n), Value::String(s)) | (Value::String(s), Value::Number(n)) => {
+                s.parse::<f64>().ok() == n.as_f64()
+            }
+            (a, b) => a == b,
+        }
+    }
+";
+
+// Raw string literal of `text`, with enough hashes not to be closed by it
+fn raw_string(text: &str) -> String {
+    let mut hashes = String::from("#");
+    while text.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{}\"{}\"{}", hashes, text, hashes)
+}
+
+// Call giving an optional parameter of type `ty` its default value when it is left
+// out, unless the value doesn't fit the type, such as `-1` for a `u64`
+fn unwrap_default(default: &Literal, ty: &TypeExpr) -> Option<String> {
+    let value = match (default, ty) {
+        (Literal::String(value), TypeExpr::Primitive(Primitive::String)) => {
+            return Some(format!("unwrap_or_else(|| String::from({:?}))", value));
+        }
+        (Literal::Boolean(value), TypeExpr::Primitive(Primitive::Boolean)) => value.to_string(),
+        (Literal::Number(value), TypeExpr::Primitive(Primitive::Number(number))) => {
+            let value = *value;
+            let fits = match number.unwrap_or_default() {
+                NumberType::F64 => true,
+                NumberType::I64 => value.fract() == 0.0,
+                NumberType::U64 => value.fract() == 0.0 && value >= 0.0,
+                NumberType::U32 => value.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&value),
+                NumberType::I32 => {
+                    value.fract() == 0.0 && (i32::MIN as f64..=i32::MAX as f64).contains(&value)
+                }
+            };
+            match number.unwrap_or_default() {
+                _ if !fits => return None,
+                NumberType::F64 => format!("{:?}", value),
+                _ => (value as i64).to_string(),
+            }
+        }
+        _ => return None,
+    };
+    Some(format!("unwrap_or({})", value))
+}
+
+/// Visibility of generated Rust items
+#[derive(Clone, Copy, Debug)]
+pub enum Visibility {
+    Private,
+    Crate,
+    Public,
+}
+
+impl Visibility {
+    fn prefix(&self) -> &'static str {
+        match self {
+            Visibility::Private => "",
+            Visibility::Crate => "pub(crate) ",
+            Visibility::Public => "pub ",
+        }
+    }
+}
+
+/// Map type used for index signatures and `Record`
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum MapType {
+    HashMap,
+    BTreeMap,
+}
+
+impl MapType {
+    fn name(&self) -> &'static str {
+        match self {
+            MapType::HashMap => "HashMap",
+            MapType::BTreeMap => "BTreeMap",
+        }
+    }
+}
+
+/// Options of the Rust serde output
+#[derive(Clone, Debug)]
+pub struct RustOptions {
+    /// Visibility of the fields of the generated structs
+    pub visibility: Visibility,
+    /// Traits derived in addition to `Debug`, `Serialize` and `Deserialize`
+    pub derives: Vec<String>,
+    /// Traits derived on single types, by type name
+    pub type_derives: HashMap<String, Vec<String>>,
+    /// Derive every standard trait that the field types of each type allow
+    pub infer_derives: bool,
+    pub map: MapType,
+}
+
+impl Default for RustOptions {
+    fn default() -> Self {
+        RustOptions {
+            visibility: Visibility::Private,
+            derives: Vec::new(),
+            type_derives: HashMap::new(),
+            infer_derives: false,
+            map: MapType::HashMap,
+        }
+    }
+}
+
+// Serde types and JSON-RPC client methods
+#[derive(Default)]
+pub struct RustBackend {
+    options: RustOptions,
+    // Inferred derives of each type of the module
+    inferred: HashMap<String, Vec<&'static str>>,
+    // Types declared in the sibling modules, mapped to their module
+    external_types: Rc<HashMap<String, String>>,
+}
+
+impl RustBackend {
+    pub fn new(
+        options: RustOptions,
+        module: &Module,
+        external_types: Rc<HashMap<String, String>>,
+    ) -> RustBackend {
+        let inferred = if options.infer_derives {
+            infer_derives(module, options.map)
+        } else {
+            HashMap::new()
+        };
+        RustBackend {
+            options,
+            inferred,
+            external_types,
+        }
+    }
+
+    fn derives(&self, name: &str) -> Vec<&str> {
+        let mut derives = vec!["Debug", "Serialize", "Deserialize"];
+        if let Some(inferred) = self.inferred.get(name) {
+            derives.extend(inferred);
+        }
+        let extra = self.options.type_derives.get(name).into_iter().flatten();
+        for derive in self.options.derives.iter().chain(extra) {
+            if !derives.contains(&derive.as_str()) {
+                derives.push(derive);
+            }
+        }
+        derives
+    }
+
+    fn derive_attribute(&self, name: &str) -> String {
+        format!("#[derive({})]\n", self.derives(name).join(", "))
+    }
+
+    fn name(def: &TypeDef) -> String {
+        if def.generics.is_empty() {
+            def.name.clone()
+        } else {
+            format!("{}<{}>", def.name, def.generics.join(", "))
+        }
+    }
+
+    fn type_ref(name: &str) -> String {
+        match name {
+            "BigNumber" => String::from("Decimal"),
+            // Dates are sent as ISO 8601 strings in JSON
+            "Date" => String::from("String"),
+            _ => name.to_string(),
+        }
+    }
+}
+
+impl Backend for RustBackend {
+    fn render_type(&self, ty: &TypeExpr) -> String {
+        match ty {
+            TypeExpr::Primitive(primitive) => String::from(match primitive {
+                Primitive::String => "String",
+                Primitive::Number(ty) => ty.unwrap_or_default().rust(),
+                Primitive::Boolean => "bool",
+                Primitive::Any => "serde_json::Value",
+                Primitive::Object => "serde_json::Map<String, serde_json::Value>",
+            }),
+            TypeExpr::Array(elem) => format!("Vec<{}>", self.render_type(elem)),
+            TypeExpr::Map(key, value) => format!(
+                "{}<{}, {}>",
+                self.options.map.name(),
+                self.render_type(key),
+                self.render_type(value)
+            ),
+            TypeExpr::Ref { name, args } if args.is_empty() => Self::type_ref(name),
+            TypeExpr::Ref { name, args } => {
+                let args: Vec<String> = args.iter().map(|arg| self.render_type(arg)).collect();
+                format!("{}<{}>", Self::type_ref(name), args.join(", "))
+            }
+            // Unions of distinct types are left to serde_json
+            TypeExpr::Union(_) => String::from("serde_json::Value"),
+            TypeExpr::Tuple(elems) => {
+                let elems: Vec<String> = elems.iter().map(|elem| self.render_type(elem)).collect();
+                format!("({})", elems.join(", "))
+            }
+            TypeExpr::Optional(ty) => format!("Option<{}>", self.render_type(ty)),
+            TypeExpr::Custom {
+                rust: Some(rust), ..
+            } => rust.clone(),
+            TypeExpr::Custom { ty, .. } => self.render_type(ty),
+        }
+    }
+
+    fn render_field(&self, field: &Field) -> String {
+        format!(
+            "{}{}: {},",
+            self.options.visibility.prefix(),
+            field_ident(field),
+            self.render_type(&field.ty)
+        )
+    }
+
+    fn render_struct(&self, def: &TypeDef, fields: &[Field]) -> String {
+        let mut out = doc_comment(&def.doc.text, "");
+        out.push_str(&self.derive_attribute(&def.name));
+        out.push_str("#[serde(rename_all = \"camelCase\")]\n");
+        out.push_str(&format!("pub struct {} {{\n", Self::name(def)));
+        for field in fields {
+            out.push_str(&doc_comment(&field.doc.text, "  "));
+            out.push_str(&deprecated_attribute(&field.doc, "  "));
+            if needs_rename(field) {
+                out.push_str(&format!("  #[serde(rename = \"{}\")]\n", field.name));
+            }
+            out.push_str(&format!("  {}\n", self.render_field(field)));
+        }
+        out.push_str("}\n\n");
+        out
+    }
+
+    fn render_map(&self, def: &TypeDef, key: &TypeExpr, value: &TypeExpr) -> String {
+        let mut out = doc_comment(&def.doc.text, "");
+        out.push_str(&self.derive_attribute(&def.name));
+        out.push_str(&format!(
+            "pub struct {}({}{}<{}, {}>);\n\n",
+            Self::name(def),
+            self.options.visibility.prefix(),
+            self.options.map.name(),
+            self.render_type(key),
+            self.render_type(value)
+        ));
+        out
+    }
+
+    // String enums keep the value of each member as its name in JSON, and enums whose
+    // members are all integers are serialized as their discriminant.
+    fn render_enum(&self, def: &TypeDef, members: &[Variant]) -> String {
+        let numbers: Option<Vec<i64>> = members
+            .iter()
+            .map(|member| match member.value {
+                Some(Literal::Number(number)) if number.fract() == 0.0 => Some(number as i64),
+                _ => None,
+            })
+            .collect();
+        let numbers = numbers.filter(|numbers| !numbers.is_empty());
+
+        let mut derives = self.derives(&def.name);
+        let mut out = doc_comment(&def.doc.text, "");
+        if numbers.is_some() {
+            // Values are converted with `into`, which takes them by value
+            if !derives.contains(&"Clone") {
+                derives.insert(1, "Clone");
+            }
+            out.push_str(&format!("#[derive({})]\n", derives.join(", ")));
+            out.push_str("#[serde(into = \"i64\", try_from = \"i64\")]\n");
+            out.push_str("#[repr(i64)]\n");
+        } else {
+            out.push_str(&format!("#[derive({})]\n", derives.join(", ")));
+        }
+        out.push_str(&format!("pub enum {} {{\n", def.name));
+        // Deriving `Default` requires to mark the default variant
+        let default = derives.contains(&"Default");
+        let variants: Vec<String> = members
+            .iter()
+            .map(|member| rust_ident(&member.name.to_case(Case::Pascal)))
+            .collect();
+        for (i, (member, variant)) in members.iter().zip(&variants).enumerate() {
+            out.push_str(&doc_comment(&member.doc.text, "  "));
+            out.push_str(&deprecated_attribute(&member.doc, "  "));
+            if default && i == 0 {
+                out.push_str("  #[default]\n");
+            }
+            match (&numbers, &member.value) {
+                (Some(numbers), _) => {
+                    out.push_str(&format!("  {} = {},\n", variant, numbers[i]));
+                    continue;
+                }
+                (None, Some(Literal::String(value)))
+                    if value != variant.trim_start_matches("r#") =>
+                {
+                    out.push_str(&format!("  #[serde(rename = {:?})]\n", value));
+                }
+                _ => (),
+            }
+            out.push_str(&format!("  {},\n", variant));
+        }
+        out.push_str("}\n\n");
+
+        if let Some(numbers) = numbers {
+            let name = &def.name;
+            out.push_str(&format!("impl From<{}> for i64 {{\n", name));
+            out.push_str(&format!("  fn from(value: {}) -> Self {{\n", name));
+            out.push_str("    value as i64\n  }\n}\n\n");
+            if members.iter().any(|member| member.doc.deprecated.is_some()) {
+                out.push_str("#[allow(deprecated)]\n");
+            }
+            out.push_str(&format!("impl TryFrom<i64> for {} {{\n", name));
+            out.push_str("  type Error = String;\n\n");
+            out.push_str("  fn try_from(value: i64) -> std::result::Result<Self, Self::Error> {\n");
+            out.push_str("    match value {\n");
+            for (number, variant) in numbers.iter().zip(&variants) {
+                out.push_str(&format!("      {} => Ok({}::{}),\n", number, name, variant));
+            }
+            out.push_str(&format!(
+                "      _ => Err(format!(\"unknown {} value {{}}\", value)),\n",
+                name
+            ));
+            out.push_str("    }\n  }\n}\n\n");
+        }
+        out
+    }
+
+    fn render_alias(&self, def: &TypeDef, ty: &TypeExpr) -> String {
+        format!(
+            "{}pub type {} = {};\n\n",
+            doc_comment(&def.doc.text, ""),
+            Self::name(def),
+            self.render_type(ty)
+        )
+    }
+
+    // Notice that the file is generated, followed by the imports of the types
+    // that are actually used in the module.
+    fn header(&self, source: &str, module: &Module, _body: &str) -> String {
+        let mut header = format!(
+            "// Generated by parser_jellyfish_rust from {}, do not edit.\n\n",
+            source
+        );
+
+        // Types mapped by the configuration may replace the maps and decimals, so the
+        // imports are looked up in the rendered types. Paths are kept whole, as types
+        // written with their path need no import.
+        let rendered: Vec<String> = module
+            .field_types()
+            .iter()
+            .map(|(_, ty)| self.render_type(ty))
+            .collect();
+        let words: HashSet<&str> = rendered
+            .iter()
+            .flat_map(|ty| ty.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')))
+            .collect();
+        let map = self.options.map.name();
+        let decimal = words.contains("Decimal");
+        let serde = module.types().any(|def| {
+            matches!(
+                def.kind,
+                TypeDefKind::Struct(_) | TypeDefKind::Map { .. } | TypeDefKind::Enum(_)
+            )
+        });
+
+        // Types of the sibling modules, unless declared in this one
+        let mut siblings: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (name, sibling) in self.external_types.iter() {
+            if words.contains(name.as_str()) && module.types().all(|def| &def.name != name) {
+                siblings.entry(sibling).or_default().push(name);
+            }
+        }
+
+        let mut imports = Vec::new();
+        if decimal {
+            imports.push(String::from("use rust_decimal::Decimal;\n"));
+        }
+        if serde {
+            imports.push(String::from("use serde::{Deserialize, Serialize};\n"));
+        }
+        if words.contains(map) {
+            imports.push(format!("use std::collections::{};\n", map));
+        }
+        for (sibling, mut names) in siblings {
+            names.sort_unstable();
+            imports.push(match names.as_slice() {
+                [name] => format!("use super::{}::{};\n", sibling, name),
+                names => format!("use super::{}::{{{}}};\n", sibling, names.join(", ")),
+            });
+        }
+        if !imports.is_empty() {
+            header.push_str(&imports.concat());
+            header.push('\n');
+        }
+        header
+    }
+
+    // Round-trip tests of the `@example` payloads of the module. The parameters of a
+    // method are deserialized as a tuple, as they are sent positionally.
+    fn footer(&self, module: &Module) -> String {
+        // Name of each test, along with the round-trip function called with the example
+        // and its other arguments
+        let mut tests: Vec<(String, String, &str, String)> = Vec::new();
+        for def in module.types() {
+            // Generic types can't be tested without their arguments
+            if !def.generics.is_empty() {
+                continue;
+            }
+            for example in &def.doc.examples {
+                let name = def.name.to_case(Case::Snake);
+                let call = format!("round_trip::<{}>", def.name);
+                tests.push((name, call, &example.json, String::new()));
+            }
+        }
+        for method in module.services().flat_map(|service| &service.methods) {
+            for example in &method.doc.examples {
+                let name = method.name.to_case(Case::Snake);
+                let (name, call, args) = match example.request {
+                    true if method.params.is_empty() => continue,
+                    true => {
+                        let params: Vec<String> = method
+                            .params
+                            .iter()
+                            .map(|param| self.render_type(&param.ty))
+                            .collect();
+                        let ty = match params.len() {
+                            1 => format!("({},)", params[0]),
+                            _ => format!("({})", params.join(", ")),
+                        };
+                        let call = format!("round_trip_params::<{}>", ty);
+                        let args = format!(", {}", params.len());
+                        (format!("{}_request", name), call, args)
+                    }
+                    false => {
+                        let ty = self.render_type(&method.returns);
+                        let call = format!("round_trip::<{}>", ty);
+                        (format!("{}_result", name), call, String::new())
+                    }
+                };
+                tests.push((name, call, &example.json, args));
+            }
+        }
+        if tests.is_empty() {
+            return String::new();
+        }
+
+        let mut out = String::from(EXAMPLES_HEADER);
+        if tests
+            .iter()
+            .any(|(_, call, _, _)| call.starts_with("round_trip::"))
+        {
+            out.push_str(ROUND_TRIP);
+        }
+        if tests
+            .iter()
+            .any(|(_, call, _, _)| call.starts_with("round_trip_params::"))
+        {
+            out.push_str(ROUND_TRIP_PARAMS);
+        }
+        out.push_str(EXAMPLES_CHECK);
+        for (i, (name, call, json, args)) in tests.iter().enumerate() {
+            // Examples of the same declaration are numbered
+            let same: Vec<usize> = (0..tests.len()).filter(|&j| tests[j].0 == *name).collect();
+            let name = if same.len() > 1 {
+                let n = same.iter().position(|&j| j == i).unwrap_or_default() + 1;
+                format!("{}_example_{}", name, n)
+            } else {
+                format!("{}_example", name)
+            };
+            out.push_str(&format!(
+                "\n    #[test]\n    fn {}() {{\n        {}({}{});\n    }}\n",
+                name,
+                call,
+                raw_string(json),
+                args
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn render_method(&self, method: &Method) -> String {
+        let mut out = doc_comment(&method_doc(method), "");
+        out.push_str(&deprecated_attribute(&method.doc, ""));
+        let mut signature = format!(
+            "pub async fn {}(&self",
+            rust_ident(&method.name.to_case(Case::Snake))
+        );
+        for param in &method.params {
+            signature.push_str(&format!(
+                ", {}: {}",
+                rust_ident(&param.name.to_case(Case::Snake)),
+                self.render_type(&param.ty)
+            ));
+        }
+        out.push_str(&format!(
+            "{}) -> Result<{}> {{\n",
+            signature,
+            self.render_type(&method.returns)
+        ));
+
+        // Parameters that are left out take their default value, if they have one, and
+        // are sent as `null` otherwise
+        for param in &method.params {
+            let unwrap = match (&param.default, &param.ty) {
+                (Some(default), TypeExpr::Optional(ty)) => unwrap_default(default, ty),
+                _ => None,
+            };
+            if let Some(unwrap) = unwrap {
+                let name = rust_ident(&param.name.to_case(Case::Snake));
+                out.push_str(&format!("\tlet {} = {}.{};\n", name, name, unwrap));
+            }
+        }
+
+        let args: Vec<String> = method
+            .args
+            .iter()
+            .map(|arg| {
+                let path: Vec<String> = arg
+                    .split('.')
+                    .map(|part| rust_ident(&part.to_case(Case::Snake)))
+                    .collect();
+                format!("{}.into()", path.join("."))
+            })
+            .collect();
+        match &method.cmd {
+            Some(cmd) => out.push_str(&format!(
+                "\tself.call(\"{}\", &[{}]).await\n",
+                cmd,
+                args.join(", ")
+            )),
+            // Methods that don't forward to a command are stubbed
+            None => out.push_str(&format!(
+                "\tunimplemented!(\"no JSON-RPC command for `{}`\")\n",
+                method.name
+            )),
+        }
+        out.push_str("}\n\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, rename: Option<&str>) -> Field {
+        let mut field = Field::new(name.to_string(), TypeExpr::Primitive(Primitive::String));
+        field.rename = rename.map(str::to_string);
+        field
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(rust_ident("type"), "r#type");
+        assert_eq!(rust_ident("gen"), "r#gen");
+        assert_eq!(rust_ident("self"), "self_");
+        assert_eq!(rust_ident("Self"), "Self_");
+        assert_eq!(rust_ident("crate"), "crate_");
+        assert_eq!(rust_ident("types"), "types");
+    }
+
+    #[test]
+    fn field_idents() {
+        assert_eq!(field_ident(&field("minColRatio", None)), "min_col_ratio");
+        assert_eq!(field_ident(&field("min_col_ratio", None)), "min_col_ratio");
+        assert_eq!(field_ident(&field("type", None)), "r#type");
+        assert_eq!(field_ident(&field("id", Some("vaultId"))), "vault_id");
+    }
+
+    #[test]
+    fn renames() {
+        // Keys written back by `rename_all = "camelCase"`
+        assert!(!needs_rename(&field("minColRatio", None)));
+        assert!(!needs_rename(&field("id", None)));
+        // Raw identifiers keep their key
+        assert!(!needs_rename(&field("type", None)));
+        // Keys that don't survive the round-trip through snake case
+        assert!(needs_rename(&field("TXID", None)));
+        assert!(needs_rename(&field("blocks_count", None)));
+        assert!(needs_rename(&field("URL", None)));
+        // Renamed fields, and keywords escaped with a suffix
+        assert!(needs_rename(&field("id", Some("vaultId"))));
+        assert!(needs_rename(&field("self", None)));
+    }
+}
@@ -0,0 +1,671 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+// Minimal model of a proto file, restricted to what matters on the wire.
+#[derive(Default)]
+struct Schema {
+    messages: BTreeMap<String, Message>,
+    enums: BTreeMap<String, Enum>,
+}
+
+#[derive(Default)]
+struct Reserved {
+    ranges: Vec<(i64, i64)>,
+    names: Vec<String>,
+}
+
+impl Reserved {
+    fn contains(&self, name: &str, number: i64) -> bool {
+        self.names.iter().any(|reserved| reserved == name)
+            || self
+                .ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&number))
+    }
+}
+
+struct Field {
+    name: String,
+    // Type including the `repeated` label, e.g. `repeated string`
+    kind: String,
+    number: i64,
+}
+
+#[derive(Default)]
+struct Message {
+    fields: Vec<Field>,
+    reserved: Reserved,
+}
+
+#[derive(Default)]
+struct Enum {
+    // Values may be negative, unlike field numbers
+    values: BTreeMap<String, i64>,
+    reserved: Reserved,
+}
+
+/// Change of a proto file that breaks its existing consumers
+#[derive(Debug)]
+pub enum BreakingChange {
+    RemovedMessage(String),
+    RemovedEnum(String),
+    RemovedField {
+        message: String,
+        field: String,
+        number: i64,
+    },
+    /// The number of the field is used by a field of another name, which changes its
+    /// JSON mapping
+    RenamedField {
+        message: String,
+        number: i64,
+        previous: String,
+        current: String,
+    },
+    ChangedFieldType {
+        message: String,
+        field: String,
+        previous: String,
+        current: String,
+    },
+    ChangedFieldNumber {
+        message: String,
+        field: String,
+        previous: i64,
+        current: i64,
+    },
+    RemovedEnumValue {
+        name: String,
+        value: String,
+        number: i64,
+    },
+    ChangedEnumValueNumber {
+        name: String,
+        value: String,
+        previous: i64,
+        current: i64,
+    },
+}
+
+impl Display for BreakingChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreakingChange::RemovedMessage(name) => write!(f, "message {} was removed", name),
+            BreakingChange::RemovedEnum(name) => write!(f, "enum {} was removed", name),
+            BreakingChange::RemovedField {
+                message,
+                field,
+                number,
+            } => write!(
+                f,
+                "field {}.{} = {} was removed without being reserved",
+                message, field, number
+            ),
+            BreakingChange::RenamedField {
+                message,
+                number,
+                previous,
+                current,
+            } => write!(
+                f,
+                "field {}.{} = {} was renamed to {}",
+                message, previous, number, current
+            ),
+            BreakingChange::ChangedFieldType {
+                message,
+                field,
+                previous,
+                current,
+            } => write!(
+                f,
+                "field {}.{} changed type from `{}` to `{}`",
+                message, field, previous, current
+            ),
+            BreakingChange::ChangedFieldNumber {
+                message,
+                field,
+                previous,
+                current,
+            } => write!(
+                f,
+                "field {}.{} changed number from {} to {}",
+                message, field, previous, current
+            ),
+            BreakingChange::RemovedEnumValue {
+                name,
+                value,
+                number,
+            } => write!(
+                f,
+                "enum value {}.{} = {} was removed without being reserved",
+                name, value, number
+            ),
+            BreakingChange::ChangedEnumValueNumber {
+                name,
+                value,
+                previous,
+                current,
+            } => write!(
+                f,
+                "enum value {}.{} changed number from {} to {}",
+                name, value, previous, current
+            ),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '"' | '\'' => {
+                let mut string = String::new();
+                for next in chars.by_ref() {
+                    if next == c {
+                        break;
+                    }
+                    string.push(next);
+                }
+                tokens.push(string);
+            }
+            // Keep generic types such as `map<string, Foo>` in a single token
+            '<' => {
+                token.push(c);
+                for c in chars.by_ref() {
+                    if !c.is_whitespace() {
+                        token.push(c);
+                    }
+                    if c == '>' {
+                        break;
+                    }
+                }
+            }
+            '{' | '}' | ';' | '=' | ',' | '[' | ']' => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+                tokens.push(c.to_string());
+            }
+            c if c.is_whitespace() => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+struct ProtoParser {
+    tokens: std::vec::IntoIter<String>,
+}
+
+impl ProtoParser {
+    fn next(&mut self) -> Option<String> {
+        self.tokens.next()
+    }
+
+    // Skip the current statement, including any block it opens
+    fn skip_statement(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.next() {
+            match token.as_str() {
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth <= 0 {
+                        return;
+                    }
+                }
+                ";" if depth == 0 => return,
+                _ => (),
+            }
+        }
+    }
+
+    // Tokens until the end of the current statement, excluding the `;`
+    fn statement(&mut self) -> Vec<String> {
+        let mut tokens = Vec::new();
+        for token in self.tokens.by_ref() {
+            if token == ";" {
+                break;
+            }
+            tokens.push(token);
+        }
+        tokens
+    }
+
+    fn reserved(&mut self, reserved: &mut Reserved) {
+        let tokens = self.statement();
+        let mut it = tokens.iter().filter(|token| *token != ",").peekable();
+        while let Some(token) = it.next() {
+            match token.parse::<i64>() {
+                Ok(start) => {
+                    let end = if it.peek().map(|token| token.as_str()) == Some("to") {
+                        it.next();
+                        match it.next().map(|token| token.as_str()) {
+                            Some("max") => i64::MAX,
+                            end => end.and_then(|end| end.parse().ok()).unwrap_or(start),
+                        }
+                    } else {
+                        start
+                    };
+                    reserved.ranges.push((start, end));
+                }
+                Err(_) => reserved.names.push(token.clone()),
+            }
+        }
+    }
+
+    fn parse(&mut self, schema: &mut Schema, scope: &str) {
+        while let Some(token) = self.next() {
+            match token.as_str() {
+                "message" => {
+                    let name = self.qualified_name(scope);
+                    self.message(schema, name);
+                }
+                "enum" => {
+                    let name = self.qualified_name(scope);
+                    self.enumeration(schema, name);
+                }
+                "}" => return,
+                ";" => (),
+                _ => self.skip_statement(),
+            }
+        }
+    }
+
+    fn qualified_name(&mut self, scope: &str) -> String {
+        let name = self.next().unwrap_or_default();
+        // Consume the opening brace
+        self.next();
+        if scope.is_empty() {
+            name
+        } else {
+            format!("{}.{}", scope, name)
+        }
+    }
+
+    fn message(&mut self, schema: &mut Schema, name: String) {
+        let mut message = Message::default();
+        while let Some(token) = self.next() {
+            match token.as_str() {
+                "}" => break,
+                ";" => (),
+                "message" => {
+                    let nested = self.qualified_name(&name);
+                    self.message(schema, nested);
+                }
+                "enum" => {
+                    let nested = self.qualified_name(&name);
+                    self.enumeration(schema, nested);
+                }
+                "reserved" => self.reserved(&mut message.reserved),
+                "option" | "extensions" => self.skip_statement(),
+                "oneof" => {
+                    // Fields of a oneof belong to the enclosing message
+                    self.next();
+                    self.next();
+                    while let Some(token) = self.next() {
+                        if token == "}" {
+                            break;
+                        }
+                        let mut tokens = vec![token];
+                        tokens.extend(self.statement());
+                        message.fields.extend(Self::field(tokens));
+                    }
+                }
+                _ => {
+                    let mut tokens = vec![token];
+                    tokens.extend(self.statement());
+                    message.fields.extend(Self::field(tokens));
+                }
+            }
+        }
+        schema.messages.insert(name, message);
+    }
+
+    // Parse `[label] type name = number [options]`
+    fn field(tokens: Vec<String>) -> Option<Field> {
+        let eq = tokens.iter().position(|token| token == "=")?;
+        if eq < 2 {
+            return None;
+        }
+        let number = tokens.get(eq + 1)?.parse().ok()?;
+        let name = tokens[eq - 1].clone();
+        let kind = tokens[..eq - 1]
+            .iter()
+            .filter(|token| *token != "optional" && *token != "required")
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(" ");
+        Some(Field { name, kind, number })
+    }
+
+    fn enumeration(&mut self, schema: &mut Schema, name: String) {
+        let mut enumeration = Enum::default();
+        while let Some(token) = self.next() {
+            match token.as_str() {
+                "}" => break,
+                ";" => (),
+                "reserved" => self.reserved(&mut enumeration.reserved),
+                "option" => self.skip_statement(),
+                _ => {
+                    let tokens = self.statement();
+                    if let (Some("="), Some(Ok(number))) = (
+                        tokens.first().map(|token| token.as_str()),
+                        tokens.get(1).map(|token| token.parse::<i64>()),
+                    ) {
+                        enumeration.values.insert(token, number);
+                    }
+                }
+            }
+        }
+        schema.enums.insert(name, enumeration);
+    }
+}
+
+fn parse_schema(source: &str) -> Schema {
+    let mut schema = Schema::default();
+    ProtoParser {
+        tokens: tokenize(source).into_iter(),
+    }
+    .parse(&mut schema, "");
+    schema
+}
+
+/// Compare a previously generated proto file with the current one and list the
+/// changes that would break existing consumers on the wire.
+pub fn check_breaking_changes(previous: &str, current: &str) -> Vec<BreakingChange> {
+    let previous = parse_schema(previous);
+    let current = parse_schema(current);
+    let mut changes = Vec::new();
+
+    for (name, message) in &previous.messages {
+        let current_message = match current.messages.get(name) {
+            Some(current_message) => current_message,
+            None => {
+                changes.push(BreakingChange::RemovedMessage(name.clone()));
+                continue;
+            }
+        };
+
+        // Fields are identified by their number on the wire. A field whose number is no
+        // longer used was renumbered if a field still has its name, and removed otherwise.
+        for field in &message.fields {
+            let current_field = match current_message
+                .fields
+                .iter()
+                .find(|f| f.number == field.number)
+            {
+                Some(current_field) => {
+                    if current_field.name != field.name {
+                        changes.push(BreakingChange::RenamedField {
+                            message: name.clone(),
+                            number: field.number,
+                            previous: field.name.clone(),
+                            current: current_field.name.clone(),
+                        });
+                    }
+                    current_field
+                }
+                None => match current_message.fields.iter().find(|f| f.name == field.name) {
+                    Some(current_field) => {
+                        changes.push(BreakingChange::ChangedFieldNumber {
+                            message: name.clone(),
+                            field: field.name.clone(),
+                            previous: field.number,
+                            current: current_field.number,
+                        });
+                        current_field
+                    }
+                    None => {
+                        if !current_message.reserved.contains(&field.name, field.number) {
+                            changes.push(BreakingChange::RemovedField {
+                                message: name.clone(),
+                                field: field.name.clone(),
+                                number: field.number,
+                            });
+                        }
+                        continue;
+                    }
+                },
+            };
+            if current_field.kind != field.kind {
+                changes.push(BreakingChange::ChangedFieldType {
+                    message: name.clone(),
+                    field: field.name.clone(),
+                    previous: field.kind.clone(),
+                    current: current_field.kind.clone(),
+                });
+            }
+        }
+    }
+
+    for (name, enumeration) in &previous.enums {
+        let current_enum = match current.enums.get(name) {
+            Some(current_enum) => current_enum,
+            None => {
+                changes.push(BreakingChange::RemovedEnum(name.clone()));
+                continue;
+            }
+        };
+
+        for (value, number) in &enumeration.values {
+            match current_enum.values.get(value) {
+                Some(current_number) if current_number != number => {
+                    changes.push(BreakingChange::ChangedEnumValueNumber {
+                        name: name.clone(),
+                        value: value.clone(),
+                        previous: *number,
+                        current: *current_number,
+                    })
+                }
+                Some(_) => (),
+                None => {
+                    if !current_enum.reserved.contains(value, *number) {
+                        changes.push(BreakingChange::RemovedEnumValue {
+                            name: name.clone(),
+                            value: value.clone(),
+                            number: *number,
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(previous: &str, current: &str) -> Vec<String> {
+        check_breaking_changes(previous, current)
+            .iter()
+            .map(|change| change.to_string())
+            .collect()
+    }
+
+    const LOAN: &str = "syntax = \"proto3\";
+
+package loan;
+
+// Scheme of a loan
+message LoanScheme {
+  string id = 1;
+  int64 min_col_ratio = 2;
+  optional string interest_rate = 3;
+}
+
+enum VaultState {
+  VAULT_STATE_UNKNOWN = 0;
+  VAULT_STATE_ACTIVE = 1;
+}
+";
+
+    #[test]
+    fn unchanged_schema() {
+        assert!(changes(LOAN, LOAN).is_empty());
+    }
+
+    #[test]
+    fn renumbered_field() {
+        let current = LOAN.replace("min_col_ratio = 2", "min_col_ratio = 4");
+        assert_eq!(
+            changes(LOAN, &current),
+            ["field LoanScheme.min_col_ratio changed number from 2 to 4"]
+        );
+    }
+
+    #[test]
+    fn renumbered_field_with_another_type() {
+        let current = LOAN.replace("int64 min_col_ratio = 2", "double min_col_ratio = 4");
+        assert_eq!(
+            changes(LOAN, &current),
+            [
+                "field LoanScheme.min_col_ratio changed number from 2 to 4",
+                "field LoanScheme.min_col_ratio changed type from `int64` to `double`",
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_field_type() {
+        let current = LOAN.replace("int64 min_col_ratio", "repeated int64 min_col_ratio");
+        assert_eq!(
+            changes(LOAN, &current),
+            ["field LoanScheme.min_col_ratio changed type from `int64` to `repeated int64`"]
+        );
+    }
+
+    #[test]
+    fn removed_field() {
+        let current = LOAN.replace("  int64 min_col_ratio = 2;\n", "");
+        assert_eq!(
+            changes(LOAN, &current),
+            ["field LoanScheme.min_col_ratio = 2 was removed without being reserved"]
+        );
+    }
+
+    #[test]
+    fn renamed_field() {
+        let current = LOAN.replace("min_col_ratio = 2", "min_collateral_ratio = 2");
+        assert_eq!(
+            changes(LOAN, &current),
+            ["field LoanScheme.min_col_ratio = 2 was renamed to min_collateral_ratio"]
+        );
+    }
+
+    #[test]
+    fn swapped_fields() {
+        let current = LOAN.replace("string id = 1", "string id = 3").replace(
+            "optional string interest_rate = 3",
+            "string interest_rate = 1",
+        );
+        assert_eq!(
+            changes(LOAN, &current),
+            [
+                "field LoanScheme.id = 1 was renamed to interest_rate",
+                "field LoanScheme.interest_rate = 3 was renamed to id",
+            ]
+        );
+    }
+
+    #[test]
+    fn reserved_field() {
+        for reserved in [
+            "reserved 2;",
+            "reserved 1 to max;",
+            "reserved \"min_col_ratio\";",
+        ] {
+            let current = LOAN.replace("int64 min_col_ratio = 2;", reserved);
+            assert!(changes(LOAN, &current).is_empty(), "{}", reserved);
+        }
+    }
+
+    #[test]
+    fn removed_enum_value() {
+        let current = LOAN.replace("  VAULT_STATE_ACTIVE = 1;\n", "");
+        assert_eq!(
+            changes(LOAN, &current),
+            ["enum value VaultState.VAULT_STATE_ACTIVE = 1 was removed without being reserved"]
+        );
+        let current = LOAN.replace("VAULT_STATE_ACTIVE = 1;", "reserved 1;");
+        assert!(changes(LOAN, &current).is_empty());
+    }
+
+    #[test]
+    fn negative_enum_values() {
+        let previous = LOAN.replace("VAULT_STATE_ACTIVE = 1", "VAULT_STATE_ACTIVE = -1");
+        assert!(changes(&previous, &previous).is_empty());
+        assert_eq!(
+            changes(&previous, LOAN),
+            ["enum value VaultState.VAULT_STATE_ACTIVE changed number from -1 to 1"]
+        );
+        let current = previous.replace("  VAULT_STATE_ACTIVE = -1;\n", "");
+        assert_eq!(
+            changes(&previous, &current),
+            ["enum value VaultState.VAULT_STATE_ACTIVE = -1 was removed without being reserved"]
+        );
+    }
+
+    #[test]
+    fn removed_declarations() {
+        assert_eq!(
+            changes(LOAN, "syntax = \"proto3\";"),
+            [
+                "message LoanScheme was removed",
+                "enum VaultState was removed"
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        let current = LOAN
+            .replace(
+                "  string id = 1;",
+                "  // string id = 5;\n  string id = 1; /* int64 id = 6; */",
+            )
+            .replace(
+                "message LoanScheme {",
+                "/* message LoanScheme { } */ message LoanScheme {",
+            );
+        assert!(changes(LOAN, &current).is_empty());
+        let current = LOAN.replace(
+            "  int64 min_col_ratio = 2;",
+            "  // int64 min_col_ratio = 2;",
+        );
+        assert_eq!(
+            changes(LOAN, &current),
+            ["field LoanScheme.min_col_ratio = 2 was removed without being reserved"]
+        );
+    }
+}
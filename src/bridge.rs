@@ -0,0 +1,231 @@
+use convert_case::{Case, Casing};
+
+use crate::backend::{rust_ident, Backend, ProtoOptions, ProtobufBackend, RustBackend};
+use crate::conversions::{prost_path, rpc_fields, Conversions, Direction, Unconvertible};
+use crate::ir::{Method, Module, Service};
+
+const STATUS_CONVERSION: &str = "impl From<ConversionError> for tonic::Status {
+    fn from(e: ConversionError) -> Self {
+        tonic::Status::invalid_argument(e.to_string())
+    }
+}
+";
+
+struct Bridge {
+    conversions: Conversions,
+    rust: RustBackend,
+    proto: ProtobufBackend,
+}
+
+impl Bridge {
+    // Implementation of a single rpc, along with the conversion of its request into
+    // the parameters of the client method and of the client result into the
+    // `<Method>Response` wrapper when there is one. Rpcs whose parameters or result
+    // can't be converted are left unimplemented, along with the kind of the field.
+    fn print_rpc(&self, method: &Method) -> (String, String, Option<&'static str>) {
+        let mut out = String::new();
+        let mut conversion = String::new();
+
+        let name = rust_ident(&method.name.to_case(Case::Snake));
+        let rpc_name = method.name.to_case(Case::Pascal);
+        let returns = self.rust.render_type(&method.returns);
+        let (params, result) = rpc_fields(method, &self.rust, &self.proto);
+        let unsupported = params
+            .iter()
+            .chain([&result])
+            .find_map(|field| self.conversions.unsupported(field));
+
+        let message = self.proto.is_message(&method.returns);
+        let response = if message {
+            prost_path(&self.proto.render_type(&method.returns))
+        } else {
+            format!("proto::{}Response", rpc_name)
+        };
+
+        // The client method is deprecated along with the rpc
+        if method.doc.deprecated.is_some() && unsupported.is_none() {
+            out.push_str("    #[allow(deprecated)]\n");
+        }
+        out.push_str(&format!("    async fn {}(\n", name));
+        out.push_str("        &self,\n");
+        let request = if method.params.is_empty() || unsupported.is_some() {
+            "_request"
+        } else {
+            "request"
+        };
+        out.push_str(&format!(
+            "        {}: tonic::Request<proto::{}Request>,\n",
+            request, rpc_name
+        ));
+        out.push_str(&format!(
+            "    ) -> std::result::Result<tonic::Response<{}>, tonic::Status> {{\n",
+            response
+        ));
+
+        if let Some(kind) = unsupported {
+            out.push_str(&format!(
+                "        // The parameters or the result of `{}` can't be converted ({})\n",
+                method.name, kind
+            ));
+            out.push_str(&format!(
+                "        Err(tonic::Status::unimplemented(\"`{}` can't be forwarded\"))\n",
+                rpc_name
+            ));
+            out.push_str("    }\n");
+            return (out, conversion, unsupported);
+        }
+
+        let args: Vec<String> = method
+            .params
+            .iter()
+            .map(|param| rust_ident(&param.name.to_case(Case::Snake)))
+            .collect();
+        if !args.is_empty() {
+            let params_fn = format!("{}_params", method.name.to_case(Case::Snake));
+            let tuple = |items: Vec<String>| match items.len() {
+                1 => format!("({},)", items[0]),
+                _ => format!("({})", items.join(", ")),
+            };
+            out.push_str(&format!(
+                "        let {} = {}(request.into_inner())?;\n",
+                tuple(args.clone()),
+                params_fn
+            ));
+
+            // Conversions of the request are gathered in a function, where they can
+            // fail with a `ConversionError`
+            let types = method
+                .params
+                .iter()
+                .map(|param| self.rust.render_type(&param.ty))
+                .collect();
+            conversion.push_str(&format!(
+                "// Parameters of `Client::{}`, converted from the rpc request\n",
+                name
+            ));
+            if method.doc.deprecated.is_some() {
+                conversion.push_str("#[allow(deprecated)]\n");
+            }
+            conversion.push_str(&format!("fn {}(\n", params_fn));
+            conversion.push_str(&format!("    request: proto::{}Request,\n", rpc_name));
+            conversion.push_str(&format!(
+                ") -> std::result::Result<{}, ConversionError> {{\n",
+                tuple(types)
+            ));
+            conversion.push_str("    Ok((\n");
+            for field in &params {
+                let (value, _) = self.conversions.convert_field(
+                    field,
+                    &format!("request.{}", field.prost()),
+                    &Direction::FromProto,
+                );
+                conversion.push_str(&format!("        {},\n", value));
+            }
+            conversion.push_str("    ))\n}\n\n");
+        }
+
+        out.push_str("        let result = self\n");
+        out.push_str("            .client\n");
+        out.push_str(&format!("            .{}({})\n", name, args.join(", ")));
+        out.push_str("            .await\n");
+        out.push_str("            .map_err(|e| tonic::Status::internal(e.to_string()))?;\n");
+        out.push_str(&format!(
+            "        let response = {}::try_from(result)\n",
+            response
+        ));
+        out.push_str("            .map_err(|e| tonic::Status::internal(e.to_string()))?;\n");
+        out.push_str("        Ok(tonic::Response::new(response))\n");
+        out.push_str("    }\n");
+
+        if !message {
+            let (value, _) = self
+                .conversions
+                .convert_field(&result, "value", &Direction::ToProto);
+            conversion.push_str(&format!("impl TryFrom<{}> for {} {{\n", returns, response));
+            conversion.push_str("    type Error = ConversionError;\n\n");
+            conversion.push_str(&format!(
+                "    fn try_from(value: {}) -> std::result::Result<Self, Self::Error> {{\n",
+                returns
+            ));
+            conversion.push_str(&format!("        Ok(Self {{ result: {} }})\n", value));
+            conversion.push_str("    }\n}\n\n");
+        }
+
+        (out, conversion, None)
+    }
+
+    fn handle_service(
+        &self,
+        Service { name, methods }: &Service,
+        unconvertible: &mut Vec<Unconvertible>,
+    ) -> String {
+        let service = format!("{}Service", name);
+
+        let mut out = String::new();
+        out.push_str(&format!(
+            "// gRPC service forwarding every rpc of `{}` to the JSON-RPC client.\n",
+            name
+        ));
+        out.push_str(&format!("pub struct {} {{\n", service));
+        out.push_str("    client: Client,\n}\n\n");
+        out.push_str(&format!("impl {} {{\n", service));
+        out.push_str("    pub fn new(client: Client) -> Self {\n");
+        out.push_str(&format!("        {} {{ client }}\n", service));
+        out.push_str("    }\n}\n\n");
+
+        let mut rpcs = Vec::new();
+        let mut conversions = String::new();
+        for method in methods {
+            let (rpc, conversion, unsupported) = self.print_rpc(method);
+            rpcs.push(rpc);
+            conversions.push_str(&conversion);
+            if let Some(kind) = unsupported {
+                unconvertible.push(Unconvertible {
+                    span: method.span,
+                    kind,
+                    emitted: format!("unimplemented rpc `{}`", method.name.to_case(Case::Pascal)),
+                });
+            }
+        }
+
+        out.push_str("#[tonic::async_trait]\n");
+        out.push_str(&format!(
+            "impl proto::{}_server::{} for {} {{\n",
+            name.to_case(Case::Snake),
+            name,
+            service
+        ));
+        out.push_str(&rpcs.join("\n"));
+        out.push_str("}\n\n");
+        out.push_str(&conversions);
+        out
+    }
+}
+
+// Generate tonic service implementations forwarding each rpc generated from a
+// class to the corresponding method of the generated JSON-RPC client, along with
+// the rpcs that were left unimplemented.
+pub fn handle_bridge(
+    module: &Module,
+    options: ProtoOptions,
+    rust: RustBackend,
+) -> (String, Vec<Unconvertible>) {
+    let proto = ProtobufBackend::new(options);
+    let bridge = Bridge {
+        conversions: Conversions::new(module, &rust, &proto),
+        rust,
+        proto,
+    };
+
+    let mut out = String::new();
+    let mut unconvertible = Vec::new();
+    for service in module.services() {
+        out.push_str(&bridge.handle_service(service, &mut unconvertible));
+    }
+
+    // The conversions module holds the error and the helpers of the conversions
+    let mut header = String::from("use super::conversions::*;\nuse super::*;\n\n");
+    header.push_str(STATUS_CONVERSION);
+    header.push('\n');
+    (format!("{}{}", header, out), unconvertible)
+}
@@ -1,51 +1,44 @@
-use convert_case::{Case, Casing};
-
+use swc_common::Spanned;
 use swc_ecma_ast::{
     ArrayLit, AssignPat, AwaitExpr, BindingIdent, CallExpr, Class, ClassMember, ClassMethod, Expr,
     ExprOrSpread, Ident, Lit, MemberExpr, MemberProp, Pat, PropName, ReturnStmt, Stmt, Str,
-    TsArrayType, TsEntityName, TsType, TsTypeAnn, TsTypeParamInstantiation, TsTypeRef,
+    TsEntityName, TsType, TsTypeParamInstantiation, TsTypeRef,
 };
 
-use crate::utils::{ConversionType, Param};
+use crate::ir::{Field, Literal, Method, Primitive, Service, TypeExpr};
+use crate::utils::{lower_literal, lower_type, Context, Target};
 
-fn get_cmd_args(args: Vec<ExprOrSpread>) -> (Option<String>, Vec<String>) {
+fn get_cmd_args(args: Vec<ExprOrSpread>, cx: &mut Context) -> (Option<String>, Vec<String>) {
     let mut it = args.into_iter();
 
-    let cmd = if let Some(ExprOrSpread {
-        expr: box Expr::Lit(Lit::Str(Str { value, .. })),
-        ..
-    }) = it.next()
-    {
-        Some(value.to_string())
-    } else {
-        None
+    let cmd = match it.next().map(|arg| *arg.expr) {
+        Some(Expr::Lit(Lit::Str(Str { value, .. }))) => Some(value.to_string()),
+        _ => None,
     };
 
     let mut args = Vec::new();
-    if let Some(ExprOrSpread {
-        expr: box Expr::Array(ArrayLit { elems, .. }),
-        ..
-    }) = &it.next()
-    {
+    if let Some(Expr::Array(ArrayLit { elems, .. })) = it.next().map(|arg| *arg.expr) {
         let mut it = elems.iter();
-        while let Some(Some(ExprOrSpread { box expr, .. })) = it.next() {
+        while let Some(Some(ExprOrSpread { expr, .. })) = it.next() {
             // println!("expr : {:#?}", expr);
-            match expr {
+            let path = match &**expr {
                 Expr::Member(MemberExpr {
-                    obj: box Expr::Ident(Ident { sym, .. }),
+                    obj,
                     prop: MemberProp::Ident(prop),
                     ..
-                }) => {
-                    args.push(format!(
-                        "{}.{}.into()",
-                        sym.to_string().to_case(Case::Snake),
-                        prop.sym.to_string().to_case(Case::Snake)
-                    ));
-                }
-                Expr::Ident(Ident { sym, .. }) => {
-                    args.push(format!("{}.into()", sym.to_string().to_case(Case::Snake),));
-                }
-                _ => (),
+                }) => match &**obj {
+                    Expr::Ident(Ident { sym, .. }) => Some(format!("{}.{}", sym, prop.sym)),
+                    _ => None,
+                },
+                Expr::Ident(Ident { sym, .. }) => Some(sym.to_string()),
+                _ => None,
+            };
+            match path {
+                Some(path) => args.push(path),
+                None => cx.warn(
+                    expr.span(),
+                    String::from("call argument is not supported, skipped"),
+                ),
             }
         }
     }
@@ -53,189 +46,136 @@ fn get_cmd_args(args: Vec<ExprOrSpread>) -> (Option<String>, Vec<String>) {
     (cmd, args)
 }
 
-pub fn handle_class(class: Class, conversion_type: ConversionType) {
-    for member in class.body {
-        let mut fn_name: Option<String> = None;
-        let mut fn_params: Vec<Param> = Vec::new();
-        let mut fn_return_type: Option<String> = None;
-
-        let mut fn_cmd: Option<String> = None;
-        let mut fn_args: Vec<String> = Vec::new();
-
-        // Get method name and parameters
-        if let ClassMember::Method(ClassMethod {
+fn get_method(class: &str, method: ClassMethod, cx: &mut Context) -> Option<Method> {
+    let span = method.span;
+    let (name, function) = match method {
+        ClassMethod {
             key: PropName::Ident(ident),
             function,
             ..
-        }) = member
-        {
-            fn_name = Some(ident.sym.to_string().to_case(Case::Snake));
-            for param in function.params {
-                // println!("param.pat : {:#?}", param.pat);
-                match param.pat {
-                    Pat::Ident(BindingIdent {
-                        id,
-                        type_ann: Some(TsTypeAnn { box type_ann, .. }),
-                        ..
-                    }) => match type_ann {
-                        TsType::TsTypeRef(TsTypeRef {
-                            type_name: TsEntityName::Ident(ident),
-                            ..
-                        }) => {
-                            fn_params.push(Param {
-                                prefix: String::from(""),
-                                key: id.sym.to_string(),
-                                val: ident.sym.to_string(),
-                                optional: id.optional,
-                                conversion_type: conversion_type.get(),
-                            });
-                        }
-                        TsType::TsKeywordType(keyword) => {
-                            fn_params.push(Param {
-                                prefix: String::from(""),
-                                key: id.sym.to_string(),
-                                val: ConversionType::Rust.map_type()(keyword.kind),
-                                optional: id.optional,
-                                conversion_type: conversion_type.get(),
-                            });
-                        }
-                        _ => (),
-                    },
-                    Pat::Assign(AssignPat {
-                        left:
-                            box Pat::Ident(BindingIdent {
-                                id,
-                                type_ann: Some(TsTypeAnn { type_ann, .. }),
-                                ..
-                            }),
-                        right: _,
-                        ..
-                    }) => {
-                        match *type_ann {
-                            TsType::TsArrayType(TsArrayType {
-                                elem_type:
-                                    box TsType::TsTypeRef(TsTypeRef {
-                                        type_name: TsEntityName::Ident(ident),
-                                        ..
-                                    }),
-                                ..
-                            }) => {
-                                fn_params.push(Param {
-                                    prefix: String::from(""),
-                                    key: id.sym.to_string(),
-                                    val: ident.sym.to_string(),
-                                    optional: true,
-                                    conversion_type: conversion_type.get(),
-                                });
-                                // fn_has_utxo = true;
-                            }
-                            _ => {}
-                        }
-                    }
-                    _ => (),
-                }
+        } => (ident.sym.to_string(), function),
+        _ => {
+            cx.warn(span, String::from("method name is not supported, skipped"));
+            return None;
+        }
+    };
+    cx.annotate(span, &format!("{}.{}", class, name), Target::Method);
+    let jsdoc = cx.jsdoc(span.lo);
+
+    // Get method parameters
+    let mut params: Vec<Field> = Vec::new();
+    for param in function.params {
+        let span = param.span;
+        // Parameters with a default value are optional, their default is kept when it
+        // is a literal and left to the JSON-RPC server otherwise
+        let binding = match param.pat {
+            Pat::Ident(binding) => Some((binding, None)),
+            Pat::Assign(AssignPat { left, right, .. }) => match *left {
+                Pat::Ident(binding) => Some((binding, Some(lower_literal(&right)))),
+                _ => None,
+            },
+            _ => None,
+        };
+        let (BindingIdent { id, type_ann }, default) = match binding {
+            Some(binding) => binding,
+            None => {
+                cx.warn(
+                    span,
+                    String::from("parameter pattern is not supported, skipped"),
+                );
+                continue;
             }
-
-            // Get method arguments
-            if let Some(body) = function.body {
-                for statement in body.stmts {
-                    match statement {
-                        Stmt::Return(ReturnStmt {
-                            arg:
-                                Some(box Expr::Await(
-                                    AwaitExpr {
-                                        arg: box Expr::Call(CallExpr { args, .. }),
-                                        ..
-                                    },
-                                    ..,
-                                )),
-                            ..
-                        }) => {
-                            (fn_cmd, fn_args) = get_cmd_args(args);
-                        }
-                        _ => (),
-                    }
-                    // Some(box TsType::TsArrayType(TsArrayType {
-                    //     elem_type:
-                    //         box TsType::TsTypeRef(TsTypeRef {
-                    //             type_name: TsEntityName::Ident(_ident),
-                    //             ..
-                    //         }),
-                    //     ..
-                    // })) => {
-                }
+        };
+        let ty = match (type_ann, &default) {
+            (Some(type_ann), _) => lower_type(*type_ann.type_ann, cx),
+            // The type of an unannotated parameter is inferred from its default value
+            (None, Some(Some(default))) => TypeExpr::Primitive(match default {
+                Literal::String(_) => Primitive::String,
+                Literal::Number(_) => Primitive::Number(None),
+                Literal::Boolean(_) => Primitive::Boolean,
+            }),
+            (None, _) => {
+                cx.warn(
+                    span,
+                    format!("parameter `{}` has no type annotation, using `any`", id.sym),
+                );
+                TypeExpr::Primitive(Primitive::Any)
             }
+        };
+        let ty = if id.optional || default.is_some() {
+            ty.optional()
+        } else {
+            ty
+        };
+        let mut param = Field::new(id.sym.to_string(), ty);
+        param.default = default.flatten();
+        param.doc.text = jsdoc.param(&param.name).unwrap_or_default();
+        params.push(param);
+    }
 
-            // Get method return type
-            if let Some(TsTypeAnn { box type_ann, .. }) = function.return_type {
-                if let TsType::TsTypeRef(TsTypeRef {
-                    type_name: TsEntityName::Ident(_ident),
-                    type_params: Some(TsTypeParamInstantiation { params, .. }),
-                    ..
-                }) = type_ann
-                {
-                    match params.get(0) {
-                        Some(box TsType::TsKeywordType(keyword)) => {
-                            fn_return_type = Some(ConversionType::Rust.map_type()(keyword.kind));
-                        }
-                        Some(box TsType::TsArrayType(TsArrayType {
-                            elem_type:
-                                box TsType::TsTypeRef(TsTypeRef {
-                                    type_name: TsEntityName::Ident(_ident),
-                                    ..
-                                }),
-                            ..
-                        })) => {
-                            fn_return_type = Some(format!("Vec<{}>", _ident.sym));
-                        }
-                        Some(box TsType::TsTypeRef(TsTypeRef {
-                            type_name: TsEntityName::Ident(_ident),
-                            ..
-                        })) => {
-                            fn_return_type = Some(_ident.sym.to_string());
-                        }
-                        _ => fn_return_type = Some("NEED TO HANDLE ENUM".to_string()), // TODO Need to handle enum return
+    // Get method arguments
+    let mut cmd: Option<String> = None;
+    let mut args: Vec<String> = Vec::new();
+    if let Some(body) = function.body {
+        for statement in body.stmts {
+            if let Stmt::Return(ReturnStmt { arg: Some(arg), .. }) = statement {
+                if let Expr::Await(AwaitExpr { arg, .. }) = *arg {
+                    if let Expr::Call(CallExpr {
+                        args: call_args, ..
+                    }) = *arg
+                    {
+                        (cmd, args) = get_cmd_args(call_args, cx);
                     }
                 }
             }
+        }
+    }
 
-            let mut format_method = format!(
-                "pub async fn {}(&self",
-                fn_name.as_ref().expect("Missing fn name")
-            );
-            for param in &fn_params {
-                format_method.push_str(&format!(", {}", param));
-            }
-            format_method.push(')');
-            format_method.push_str(&format!(
-                " -> Result<{}> {{",
-                fn_return_type.as_ref().expect("Missing return type")
-            ));
-
-            println!("{}", format_method);
-
-            for param in fn_params {
-                if param.optional {
-                    println!("\tlet utxos = utxos.unwrap_or_default());");
-                }
-            }
+    if cmd.is_none() {
+        cx.warn(
+            span,
+            format!(
+                "method `{}` doesn't return a JSON-RPC call, its Rust method is a stub",
+                name
+            ),
+        );
+    }
 
-            let mut format_body = format!(
-                "\tself.call(\"{}\", &[",
-                fn_cmd.expect("Missing function command")
-            );
+    // Get method return type, unwrapping the `Promise`
+    let returns = match function.return_type.map(|type_ann| *type_ann.type_ann) {
+        Some(TsType::TsTypeRef(TsTypeRef {
+            type_name: TsEntityName::Ident(ident),
+            type_params: Some(TsTypeParamInstantiation { mut params, .. }),
+            ..
+        })) if &*ident.sym == "Promise" && params.len() == 1 => lower_type(*params.remove(0), cx),
+        Some(type_ann) => lower_type(type_ann, cx),
+        None => cx.fallback(
+            span,
+            "missing return type",
+            TypeExpr::Primitive(Primitive::Any),
+        ),
+    };
 
-            let mut it = fn_args.into_iter();
-            if let Some(arg) = it.next() {
-                format_body.push_str(&arg.to_string());
-            }
-            for arg in it {
-                format_body.push_str(&format!(", {}", arg));
-            }
+    Some(Method {
+        name,
+        params,
+        returns,
+        cmd,
+        args,
+        doc: jsdoc.doc(),
+        span,
+    })
+}
 
-            format_body.push_str("]).await");
-            println!("{}", format_body);
-            println!("}}\n");
-        }
-    }
+pub fn handle_class(ident: Ident, class: Class, cx: &mut Context) -> Service {
+    let name = ident.sym.to_string();
+    let methods: Vec<Method> = class
+        .body
+        .into_iter()
+        .filter_map(|member| match member {
+            ClassMember::Method(method) => get_method(&name, method, cx),
+            _ => None,
+        })
+        .collect();
+    Service { name, methods }
 }
@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::backend::MapType;
+use crate::ir::{Item, Module, NumberType, Primitive, TypeDefKind, TypeExpr};
+use crate::Options;
+
+/// Types used in place of a TypeScript type, written as is in the generated code.
+/// The default type is used for the formats that are left out.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mapping {
+    pub rust: Option<String>,
+    pub proto: Option<String>,
+}
+
+impl Mapping {
+    fn apply(&self, ty: &mut TypeExpr) {
+        let original = std::mem::replace(ty, TypeExpr::Primitive(Primitive::Any));
+        *ty = TypeExpr::Custom {
+            ty: Box::new(original),
+            rust: self.rust.clone(),
+            proto: self.proto.clone(),
+        };
+    }
+
+    // Apply the mapping to the element of optional and array types
+    fn apply_elem(&self, ty: &mut TypeExpr) {
+        let mut elem = ty;
+        while let TypeExpr::Optional(ty) | TypeExpr::Array(ty) = elem {
+            elem = ty;
+        }
+        self.apply(elem);
+    }
+}
+
+// Whether `name` matches `pattern`, where `*` stands for any sequence of characters.
+// Case is ignored, so that `*Ratio` matches both `ratio` and `minColRatio`.
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (pattern.to_lowercase(), name.to_lowercase());
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let mut rest = name.as_str();
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(after) => rest = after,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(at) => rest = &rest[at + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    rest.is_empty()
+}
+
+/// Representations of `number`. Fields and parameters use, in order of precedence, the
+/// one of their `@number` JSDoc tag, of their entry in `fields`, of the longest pattern
+/// of `names` matching their name, and the default.
+#[derive(Clone, Debug, Default)]
+pub struct Numbers {
+    pub default: NumberType,
+    /// Glob patterns of field and parameter names, such as `*Ratio`
+    pub names: HashMap<String, NumberType>,
+    /// Representations of single fields and parameters, as `Type.field` and
+    /// `Class.method.param`
+    pub fields: HashMap<String, NumberType>,
+}
+
+impl Numbers {
+    fn field(&self, member: &str, name: &str) -> Option<NumberType> {
+        if let Some(number) = self.fields.get(member) {
+            return Some(*number);
+        }
+        self.names
+            .iter()
+            .filter(|(pattern, _)| matches_glob(pattern, name))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, number)| *number)
+    }
+
+    fn apply(&self, module: &mut Module) {
+        for item in &mut module.items {
+            match item {
+                Item::Type(def) => {
+                    if let TypeDefKind::Struct(fields) = &mut def.kind {
+                        for field in fields {
+                            let member = format!("{}.{}", def.name, field.name);
+                            if let Some(number) = self.field(&member, &field.name) {
+                                field.ty.resolve_numbers(number);
+                            }
+                        }
+                    }
+                }
+                Item::Service(service) => {
+                    for method in &mut service.methods {
+                        for param in &mut method.params {
+                            let member = format!("{}.{}.{}", service.name, method.name, param.name);
+                            if let Some(number) = self.field(&member, &param.name) {
+                                param.ty.resolve_numbers(number);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        module.walk_types_mut(&mut |ty| {
+            if let TypeExpr::Primitive(Primitive::Number(number @ None)) = ty {
+                *number = Some(self.default);
+            }
+        });
+    }
+}
+
+/// Changes applied to the declarations of every module before they are rendered.
+/// Declarations are referred to by their TypeScript name, and their members as
+/// `Type.member`.
+#[derive(Clone, Debug, Default)]
+pub struct Overrides {
+    /// Mappings of named types and of the `string`, `number`, `boolean`, `any` and
+    /// `object` keywords
+    pub types: HashMap<String, Mapping>,
+    /// Mappings of the type of single fields, and of the return type of methods, applied
+    /// to the elements of arrays
+    pub fields: HashMap<String, Mapping>,
+    /// New names of types, classes, fields and methods
+    pub rename: HashMap<String, String>,
+    /// Types, classes, fields and methods left out of the output
+    pub skip: Vec<String>,
+    pub numbers: Numbers,
+    /// Numbers of single fields in proto, the other fields are numbered in order
+    pub proto_fields: HashMap<String, u32>,
+}
+
+fn keyword(primitive: &Primitive) -> &'static str {
+    match primitive {
+        Primitive::String => "string",
+        Primitive::Number(_) => "number",
+        Primitive::Boolean => "boolean",
+        Primitive::Any => "any",
+        Primitive::Object => "object",
+    }
+}
+
+impl Overrides {
+    fn skips(&self, name: &str) -> bool {
+        self.skip.iter().any(|skip| skip == name)
+    }
+
+    /// Add the overrides of `other`, which take precedence over the current ones. The
+    /// default representation of numbers is kept.
+    pub fn extend(&mut self, other: Overrides) {
+        self.types.extend(other.types);
+        self.fields.extend(other.fields);
+        self.rename.extend(other.rename);
+        self.skip.extend(other.skip);
+        self.numbers.names.extend(other.numbers.names);
+        self.numbers.fields.extend(other.numbers.fields);
+        self.proto_fields.extend(other.proto_fields);
+    }
+
+    pub fn apply(&self, module: &mut Module) {
+        module.items.retain(|item| match item {
+            Item::Type(def) => !self.skips(&def.name),
+            Item::Service(service) => !self.skips(&service.name),
+        });
+
+        self.numbers.apply(module);
+
+        for item in &mut module.items {
+            match item {
+                Item::Type(def) => {
+                    if let TypeDefKind::Struct(fields) = &mut def.kind {
+                        fields.retain(|field| !self.skips(&format!("{}.{}", def.name, field.name)));
+                        for field in fields {
+                            let member = format!("{}.{}", def.name, field.name);
+                            if let Some(mapping) = self.fields.get(&member) {
+                                mapping.apply_elem(&mut field.ty);
+                            }
+                            field.rename = self.rename.get(&member).cloned();
+                            field.tag = self.proto_fields.get(&member).copied();
+                        }
+                    }
+                }
+                Item::Service(service) => {
+                    let name = &service.name;
+                    service
+                        .methods
+                        .retain(|method| !self.skips(&format!("{}.{}", name, method.name)));
+                    for method in &mut service.methods {
+                        let member = format!("{}.{}", name, method.name);
+                        if let Some(mapping) = self.fields.get(&member) {
+                            mapping.apply_elem(&mut method.returns);
+                        }
+                        if let Some(rename) = self.rename.get(&member) {
+                            method.name = rename.clone();
+                        }
+                    }
+                }
+            }
+        }
+
+        module.walk_types_mut(&mut |ty| {
+            let name = match ty {
+                TypeExpr::Primitive(primitive) => keyword(primitive),
+                TypeExpr::Ref { name, .. } => name.as_str(),
+                _ => return,
+            };
+            if let Some(mapping) = self.types.get(name) {
+                mapping.apply(ty);
+            }
+        });
+
+        // Types are renamed last, as the other overrides refer to their TypeScript name
+        for item in &mut module.items {
+            let name = match item {
+                Item::Type(def) => &mut def.name,
+                Item::Service(service) => &mut service.name,
+            };
+            if let Some(rename) = self.rename.get(name.as_str()) {
+                *name = rename.clone();
+            }
+        }
+        module.walk_types_mut(&mut |ty| {
+            if let TypeExpr::Ref { name, .. } = ty {
+                if let Some(rename) = self.rename.get(name.as_str()) {
+                    *name = rename.clone();
+                }
+            }
+        });
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct NumbersConfig {
+    default: Option<NumberType>,
+    names: HashMap<String, NumberType>,
+    fields: HashMap<String, NumberType>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RustConfig {
+    derives: Vec<String>,
+    type_derives: HashMap<String, Vec<String>>,
+    map: Option<MapType>,
+}
+
+/// Project configuration, loaded from a TOML file such as
+///
+/// ```toml
+/// skip = ["LegacyInfo", "BlockchainInfo.warnings"]
+///
+/// [types]
+/// BigNumber = { rust = "rust_decimal::Decimal", proto = "string" }
+///
+/// [fields]
+/// "BlockchainInfo.difficulty" = { rust = "f64", proto = "double" }
+///
+/// [rename]
+/// "LoanScheme.minColRatio" = "minCollateralRatio"
+///
+/// [numbers]
+/// default = "i64"
+/// names = { "*Ratio" = "f64", "*Pct" = "f64" }
+/// fields = { "BlockchainInfo.verificationprogress" = "f64" }
+///
+/// [proto_fields]
+/// "BlockchainInfo.chain" = 7
+///
+/// [rust]
+/// derives = ["Clone"]
+/// type_derives = { LoanScheme = ["PartialEq"] }
+/// map = "BTreeMap"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    types: HashMap<String, Mapping>,
+    fields: HashMap<String, Mapping>,
+    rename: HashMap<String, String>,
+    skip: Vec<String>,
+    numbers: NumbersConfig,
+    proto_fields: HashMap<String, u32>,
+    rust: RustConfig,
+}
+
+impl Config {
+    pub fn from_toml(text: &str) -> Result<Config, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// Apply the configuration to `options`, in addition to the options already set
+    pub fn apply(self, options: &mut Options) {
+        let overrides = &mut options.overrides;
+        overrides.types.extend(self.types);
+        overrides.fields.extend(self.fields);
+        overrides.rename.extend(self.rename);
+        overrides.skip.extend(self.skip);
+        if let Some(default) = self.numbers.default {
+            overrides.numbers.default = default;
+        }
+        overrides.numbers.names.extend(self.numbers.names);
+        overrides.numbers.fields.extend(self.numbers.fields);
+        overrides.proto_fields.extend(self.proto_fields);
+
+        let rust = &mut options.rust;
+        rust.derives.extend(self.rust.derives);
+        rust.type_derives.extend(self.rust.type_derives);
+        if let Some(map) = self.rust.map {
+            rust.map = map;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{Backend, RustBackend, RustOptions};
+    use crate::ir::TypeDef;
+    use crate::lower_text;
+
+    // Lower `text` with the overrides of the `config` and of its JSDoc tags
+    fn apply(config: &str, text: &str) -> Module {
+        let mut options = Options::default();
+        Config::from_toml(config).unwrap().apply(&mut options);
+        let (mut module, jsdoc) = lower_text(text);
+        let mut overrides = options.overrides;
+        overrides.extend(jsdoc);
+        overrides.apply(&mut module);
+        module
+    }
+
+    fn def<'a>(module: &'a Module, name: &str) -> &'a TypeDef {
+        module.types().find(|def| def.name == name).unwrap()
+    }
+
+    // Rust type of each field of the struct `name`
+    fn rust_fields(module: &Module, name: &str) -> Vec<(String, String)> {
+        let backend = RustBackend::new(RustOptions::default(), module, Default::default());
+        match &def(module, name).kind {
+            TypeDefKind::Struct(fields) => fields
+                .iter()
+                .map(|field| {
+                    (
+                        field.code_name().to_string(),
+                        backend.render_type(&field.ty),
+                    )
+                })
+                .collect(),
+            _ => panic!("{} is not a struct", name),
+        }
+    }
+
+    #[test]
+    fn globs() {
+        assert!(matches_glob("*Ratio", "minColRatio"));
+        assert!(matches_glob("*Ratio", "ratio"));
+        assert!(matches_glob("*ratio", "MINCOLRATIO"));
+        assert!(matches_glob("min*", "minColRatio"));
+        assert!(matches_glob("*Col*", "minColRatio"));
+        assert!(matches_glob("m*Col*o", "minColRatio"));
+        assert!(matches_glob("*", "amount"));
+        assert!(matches_glob("amount", "Amount"));
+        assert!(!matches_glob("*Ratio", "ratioPct"));
+        assert!(!matches_glob("amount", "amounts"));
+        assert!(!matches_glob("min*Max", "minColRatio"));
+        // Parts can't overlap
+        assert!(!matches_glob("*ab*ba", "aba"));
+    }
+
+    #[test]
+    fn field_mappings_take_precedence() {
+        let config = "[types]\n\
+                      BigNumber = { rust = \"f64\" }\n\
+                      [fields]\n\
+                      \"Loan.interest\" = { rust = \"rust_decimal::Decimal\" }\n";
+        let text = "export interface Loan {\n  amount: BigNumber\n  interest: BigNumber\n  \
+                    history: BigNumber[]\n}\n";
+        let module = apply(config, text);
+        assert_eq!(
+            rust_fields(&module, "Loan"),
+            [
+                (String::from("amount"), String::from("f64")),
+                (
+                    String::from("interest"),
+                    String::from("rust_decimal::Decimal")
+                ),
+                (String::from("history"), String::from("Vec<f64>")),
+            ]
+        );
+    }
+
+    #[test]
+    fn field_mappings_apply_to_elements() {
+        let config = "[fields]\n\"Loan.history\" = { rust = \"f64\" }\n";
+        let text = "export interface Loan {\n  history?: BigNumber[]\n}\n";
+        let module = apply(config, text);
+        assert_eq!(
+            rust_fields(&module, "Loan"),
+            [(String::from("history"), String::from("Option<Vec<f64>>"))]
+        );
+    }
+
+    #[test]
+    fn renames() {
+        // Members are referred to by the TypeScript name of their type, even when the
+        // type is renamed
+        let config = "[rename]\n\
+                      Loan = \"LoanInfo\"\n\
+                      \"Loan.minColRatio\" = \"minCollateralRatio\"\n\
+                      Token = \"TokenInfo\"\n";
+        let text = "export interface Loan {\n  minColRatio: number\n  token: Token\n}\n\
+                    export interface Vault {\n  loans: Loan[]\n}\n";
+        let module = apply(config, text);
+        let names: Vec<&str> = module.types().map(|def| def.name.as_str()).collect();
+        assert_eq!(names, ["LoanInfo", "Vault"]);
+        // References are renamed along with the declarations, and so are the references
+        // to types declared elsewhere
+        assert_eq!(
+            rust_fields(&module, "LoanInfo"),
+            [
+                (String::from("minCollateralRatio"), String::from("u64")),
+                (String::from("token"), String::from("TokenInfo")),
+            ]
+        );
+        assert_eq!(
+            rust_fields(&module, "Vault"),
+            [(String::from("loans"), String::from("Vec<LoanInfo>"))]
+        );
+        // Renamed fields keep their key
+        match &def(&module, "LoanInfo").kind {
+            TypeDefKind::Struct(fields) => assert_eq!(fields[0].name, "minColRatio"),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn number_precedence() {
+        // The `@number` tag wins over the field entry, which wins over the patterns,
+        // the longest of which wins over the shorter ones and the default
+        let config = "[numbers]\n\
+                      default = \"i64\"\n\
+                      names = { \"*Ratio\" = \"f64\", \"*ColRatio\" = \"u32\" }\n\
+                      fields = { \"Loan.tagged\" = \"i32\", \"Loan.interestRatio\" = \"u64\" }\n";
+        let text = "export interface Loan {\n  /** @number u32 */\n  tagged: number\n  \
+                    interestRatio: number\n  minColRatio: number\n  ratio: number\n  \
+                    amount: number\n}\n";
+        let module = apply(config, text);
+        assert_eq!(
+            rust_fields(&module, "Loan"),
+            [
+                (String::from("tagged"), String::from("u32")),
+                (String::from("interestRatio"), String::from("u64")),
+                (String::from("minColRatio"), String::from("u32")),
+                (String::from("ratio"), String::from("f64")),
+                (String::from("amount"), String::from("i64")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parameter_numbers() {
+        let config = "[numbers]\n\
+                      names = { \"*Height\" = \"u32\" }\n\
+                      fields = { \"Blockchain.getBlock.verbosity\" = \"i32\" }\n";
+        let text = "export class Blockchain {\n  \
+                    getBlock(height: number, verbosity: number, count: number): string {\n    \
+                    return ''\n  }\n}\n";
+        let module = apply(config, text);
+        let backend = RustBackend::new(RustOptions::default(), &module, Default::default());
+        let params: Vec<String> = module
+            .services()
+            .flat_map(|service| &service.methods)
+            .flat_map(|method| &method.params)
+            .map(|param| backend.render_type(&param.ty))
+            .collect();
+        assert_eq!(params, ["u32", "i32", "u64"]);
+    }
+
+    #[test]
+    fn skips() {
+        let config = "skip = [\"Legacy\", \"Loan.legacy\"]\n";
+        let text = "export interface Loan {\n  id: string\n  legacy: string\n}\n\
+                    export interface Legacy {\n  id: string\n}\n";
+        let module = apply(config, text);
+        let names: Vec<&str> = module.types().map(|def| def.name.as_str()).collect();
+        assert_eq!(names, ["Loan"]);
+        assert_eq!(
+            rust_fields(&module, "Loan"),
+            [(String::from("id"), String::from("String"))]
+        );
+    }
+}
@@ -0,0 +1,674 @@
+use convert_case::{Case, Casing};
+use std::cell::Cell;
+use std::collections::HashSet;
+
+use swc_common::Span;
+
+use crate::backend::{
+    field_ident, is_proto_scalar, rust_ident, Backend, DecimalRepr, ProtoOptions, ProtobufBackend,
+    RustBackend,
+};
+use crate::ir::{self, Method, Module, TypeDef, TypeDefKind, TypeExpr};
+
+// Identifier of the prost field of `field`. prost drops the underscore suffix of the
+// escaped proto names, and escapes the Rust keywords, e.g. `message_` is `message` and
+// `enum_` is `r#enum`.
+pub fn prost_ident(field: &ir::Field) -> String {
+    rust_ident(&ProtobufBackend::field_name(field).to_case(Case::Snake))
+}
+
+// Error of the conversions, whose decimal variant is only generated along with
+// conversions of decimals, as it requires `rust_decimal`
+fn conversion_error(decimal: bool) -> String {
+    let (variant, display, from) = if decimal {
+        (
+            "    InvalidDecimal(rust_decimal::Error),\n",
+            "            ConversionError::InvalidDecimal(e) => write!(f, \"invalid decimal: {}\", e),\n",
+            "impl From<rust_decimal::Error> for ConversionError {
+    fn from(e: rust_decimal::Error) -> Self {
+        ConversionError::InvalidDecimal(e)
+    }
+}
+
+",
+        )
+    } else {
+        ("", "", "")
+    };
+    format!(
+        "#[derive(Debug)]
+pub enum ConversionError {{
+    MissingField(&'static str),
+    OutOfRange,
+{}    UnknownEnumValue(i32),
+}}
+
+impl std::fmt::Display for ConversionError {{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{
+        match self {{
+            ConversionError::MissingField(field) => write!(f, \"missing field `{{}}`\", field),
+            ConversionError::OutOfRange => write!(f, \"value out of range\"),
+{}            ConversionError::UnknownEnumValue(value) => write!(f, \"unknown enum value {{}}\", value),
+        }}
+    }}
+}}
+
+impl std::error::Error for ConversionError {{}}
+
+impl From<std::num::TryFromIntError> for ConversionError {{
+    fn from(_: std::num::TryFromIntError) -> Self {{
+        ConversionError::OutOfRange
+    }}
+}}
+
+{}impl From<std::convert::Infallible> for ConversionError {{
+    fn from(e: std::convert::Infallible) -> Self {{
+        match e {{}}
+    }}
+}}
+",
+        variant, display, from
+    )
+}
+
+const DECIMAL_CONVERSIONS: &str =
+    "pub(super) fn decimal_to_proto(value: Decimal) -> std::result::Result<proto::Decimal, ConversionError> {
+    let units = value.trunc();
+    let nanos = (value - units) * Decimal::from(1_000_000_000);
+    if !nanos.fract().is_zero() {
+        return Err(ConversionError::OutOfRange);
+    }
+    Ok(proto::Decimal {
+        units: i64::try_from(units)?,
+        nanos: i32::try_from(nanos)?,
+    })
+}
+
+pub(super) fn decimal_from_proto(value: proto::Decimal) -> std::result::Result<Decimal, ConversionError> {
+    if !(-999_999_999..=999_999_999).contains(&value.nanos) {
+        return Err(ConversionError::OutOfRange);
+    }
+    Ok(Decimal::from(value.units) + Decimal::new(value.nanos.into(), 9))
+}
+";
+
+// Conversions of the `any`, `object` and union values, which serde_json represents
+// as they are in JSON and prost as `google.protobuf` dynamic values. Numbers are
+// doubles in proto, so integers beyond 2^53 lose precision.
+const VALUE_CONVERSIONS: &str =
+    "pub(super) fn value_to_proto(value: serde_json::Value) -> prost_types::Value {
+    use prost_types::value::Kind;
+    let kind = match value {
+        serde_json::Value::Null => Kind::NullValue(prost_types::NullValue::NullValue as i32),
+        serde_json::Value::Bool(value) => Kind::BoolValue(value),
+        serde_json::Value::Number(value) => Kind::NumberValue(value.as_f64().unwrap_or_default()),
+        serde_json::Value::String(value) => Kind::StringValue(value),
+        serde_json::Value::Array(values) => Kind::ListValue(prost_types::ListValue {
+            values: values.into_iter().map(value_to_proto).collect(),
+        }),
+        serde_json::Value::Object(map) => Kind::StructValue(struct_to_proto(map)),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+pub(super) fn struct_to_proto(map: serde_json::Map<String, serde_json::Value>) -> prost_types::Struct {
+    prost_types::Struct {
+        fields: map.into_iter().map(|(k, v)| (k, value_to_proto(v))).collect(),
+    }
+}
+
+pub(super) fn value_from_proto(
+    value: prost_types::Value,
+) -> std::result::Result<serde_json::Value, ConversionError> {
+    use prost_types::value::Kind;
+    Ok(match value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::BoolValue(value)) => serde_json::Value::Bool(value),
+        Some(Kind::NumberValue(value)) => serde_json::Number::from_f64(value)
+            .map(serde_json::Value::Number)
+            .ok_or(ConversionError::OutOfRange)?,
+        Some(Kind::StringValue(value)) => serde_json::Value::String(value),
+        Some(Kind::ListValue(list)) => serde_json::Value::Array(
+            list.values
+                .into_iter()
+                .map(value_from_proto)
+                .collect::<std::result::Result<_, _>>()?,
+        ),
+        Some(Kind::StructValue(value)) => serde_json::Value::Object(struct_from_proto(value)?),
+    })
+}
+
+pub(super) fn struct_from_proto(
+    value: prost_types::Struct,
+) -> std::result::Result<serde_json::Map<String, serde_json::Value>, ConversionError> {
+    value
+        .fields
+        .into_iter()
+        .map(|(k, v)| Ok((k, value_from_proto(v)?)))
+        .collect()
+}
+";
+
+// Rust types of the dynamic values
+const JSON_VALUE: &str = "serde_json::Value";
+const JSON_OBJECT: &str = "serde_json::Map<String, serde_json::Value>";
+
+// Attribute allowing the use of deprecated items in the code that follows, when
+// `deprecated` is set
+fn allow_deprecated(deprecated: bool) -> &'static str {
+    if deprecated {
+        "#[allow(deprecated)]\n"
+    } else {
+        ""
+    }
+}
+
+pub enum Direction {
+    ToProto,
+    FromProto,
+}
+
+// Path of a proto type in the code generated by prost
+pub fn prost_path(proto: &str) -> String {
+    match proto.strip_prefix("google.protobuf.") {
+        Some(name) => format!("prost_types::{}", name),
+        None => format!("proto::{}", proto.to_case(Case::Pascal)),
+    }
+}
+
+// Rust type used by prost for a proto scalar
+fn prost_scalar(proto: &str) -> Option<&'static str> {
+    Some(match proto {
+        "double" => "f64",
+        "float" => "f32",
+        "int32" | "sint32" | "sfixed32" => "i32",
+        "int64" | "sint64" | "sfixed64" => "i64",
+        "uint32" | "fixed32" => "u32",
+        "uint64" | "fixed64" => "u64",
+        "bool" => "bool",
+        "string" => "String",
+        "bytes" => "Vec<u8>",
+        _ => return None,
+    })
+}
+
+// How the elements of a field are held
+enum Elements {
+    Single,
+    Repeated,
+    // Values of a `map` field, whose keys have the given Rust and proto types
+    Map(String, String),
+}
+
+pub struct Field {
+    name: String,
+    // Name of the field in the prost types, which prost derives from the proto name
+    prost: String,
+    rust: String,
+    proto: String,
+    elements: Elements,
+    optional: bool,
+}
+
+impl Field {
+    // Build a field from its type, rendering the Rust and proto types of its elements.
+    // Optional arrays and maps are held as they are in proto, where they can't be
+    // optional.
+    pub fn new(field: &ir::Field, rust: &RustBackend, proto: &ProtobufBackend) -> Field {
+        let name = field_ident(field);
+        let prost = prost_ident(field);
+        let (ty, optional) = field.ty.clone().split_optional();
+        let (elem, elements) = match ty {
+            TypeExpr::Array(elem) => (*elem, Elements::Repeated),
+            TypeExpr::Map(key, value) => (
+                *value,
+                Elements::Map(rust.render_type(&key), proto.render_type(&key)),
+            ),
+            ty => (ty, Elements::Single),
+        };
+        Field {
+            name,
+            prost,
+            rust: rust.render_type(&elem),
+            proto: proto.render_type(&elem),
+            elements,
+            optional,
+        }
+    }
+
+    pub fn prost(&self) -> &str {
+        &self.prost
+    }
+}
+
+// Parameters and result of an rpc, as they are converted by the bridge
+pub fn rpc_fields(
+    method: &Method,
+    rust: &RustBackend,
+    proto: &ProtobufBackend,
+) -> (Vec<Field>, Field) {
+    let params = method
+        .params
+        .iter()
+        .map(|param| Field::new(param, rust, proto))
+        .collect();
+    let result = ir::Field::new(String::from("result"), method.returns.clone());
+    (params, Field::new(&result, rust, proto))
+}
+
+pub struct Conversions {
+    enums: Vec<String>,
+    // Types that have no conversions, as they have no proto message of their own or
+    // one of their fields can't be converted
+    unconvertible: HashSet<String>,
+    // Whether the generated conversions use decimals and dynamic values, which are
+    // converted by helper functions
+    decimal: Cell<bool>,
+    values: Cell<bool>,
+}
+
+// Type without conversions, reported as a fallback of the conversions
+pub struct Unconvertible {
+    pub span: Span,
+    pub kind: &'static str,
+    pub emitted: String,
+}
+
+impl Conversions {
+    pub fn new(module: &Module, rust: &RustBackend, proto: &ProtobufBackend) -> Conversions {
+        let enums = module
+            .types()
+            .filter(|def| matches!(def.kind, TypeDefKind::Enum(_)))
+            .map(|def| def.name.clone())
+            .collect();
+        // Aliases, maps and generic types are not converted, nor the types that use them
+        let unconvertible = module
+            .types()
+            .filter(|def| {
+                !def.generics.is_empty()
+                    || matches!(def.kind, TypeDefKind::Alias(_) | TypeDefKind::Map { .. })
+            })
+            .map(|def| def.name.clone())
+            .collect();
+        let mut conversions = Conversions {
+            enums,
+            unconvertible,
+            decimal: Cell::new(false),
+            values: Cell::new(false),
+        };
+        loop {
+            let skipped: Vec<String> = module
+                .types()
+                .filter(|def| !conversions.unconvertible.contains(&def.name))
+                .filter(|def| conversions.skipped(def, rust, proto).is_some())
+                .map(|def| def.name.clone())
+                .collect();
+            if skipped.is_empty() {
+                return conversions;
+            }
+            conversions.unconvertible.extend(skipped);
+        }
+    }
+
+    // Kind of the field that prevents the conversion of a struct, if any
+    fn skipped(
+        &self,
+        def: &TypeDef,
+        rust: &RustBackend,
+        proto: &ProtobufBackend,
+    ) -> Option<(String, &'static str)> {
+        let fields = match &def.kind {
+            TypeDefKind::Struct(fields) => fields,
+            _ => return None,
+        };
+        fields.iter().find_map(|field| {
+            let converted = Field::new(field, rust, proto);
+            self.unsupported(&converted)
+                .map(|kind| (field.name.clone(), kind))
+        })
+    }
+
+    // Kind of the elements of a field when they can't be converted
+    pub fn unsupported(&self, field: &Field) -> Option<&'static str> {
+        if let Elements::Map(_, key) = &field.elements {
+            // Keys of proto maps are strings or integers
+            if !is_proto_scalar(key) || matches!(key.as_str(), "double" | "float" | "bytes") {
+                return Some("map key");
+            }
+        }
+        match (field.rust.as_str(), field.proto.as_str()) {
+            (_, "google.protobuf.Timestamp") => Some("date"),
+            (_, "google.protobuf.ListValue") => Some("tuple or nested array"),
+            (JSON_VALUE, "google.protobuf.Value") | (JSON_OBJECT, "google.protobuf.Struct") => None,
+            (_, "google.protobuf.Struct") => Some("nested map"),
+            (_, "google.protobuf.Value") => Some("mapped type"),
+            (_, proto) if self.unconvertible.contains(proto) => Some("type without conversions"),
+            _ => None,
+        }
+    }
+
+    // Expression converting `input` between the Rust and the proto type of a single
+    // element, and whether it evaluates to a `Result`.
+    fn convert(
+        &self,
+        rust: &str,
+        proto: &str,
+        input: &str,
+        direction: &Direction,
+    ) -> (String, bool) {
+        if rust == "Decimal" {
+            self.decimal.set(true);
+        }
+        match (rust, proto, direction) {
+            ("Decimal", "string", Direction::ToProto) => (format!("{}.to_string()", input), false),
+            ("Decimal", "string", Direction::FromProto) => {
+                (format!("{}.parse::<Decimal>()", input), true)
+            }
+            ("Decimal", "Decimal", Direction::ToProto) => {
+                (format!("decimal_to_proto({})", input), true)
+            }
+            ("Decimal", "Decimal", Direction::FromProto) => {
+                (format!("decimal_from_proto({})", input), true)
+            }
+            (JSON_VALUE, "google.protobuf.Value", direction) => {
+                self.values.set(true);
+                match direction {
+                    Direction::ToProto => (format!("value_to_proto({})", input), false),
+                    Direction::FromProto => (format!("value_from_proto({})", input), true),
+                }
+            }
+            (JSON_OBJECT, "google.protobuf.Struct", direction) => {
+                self.values.set(true);
+                match direction {
+                    Direction::ToProto => (format!("struct_to_proto({})", input), false),
+                    Direction::FromProto => (format!("struct_from_proto({})", input), true),
+                }
+            }
+            (rust, proto, Direction::ToProto) if self.enums.iter().any(|e| e == rust) => {
+                (format!("{}::from({}) as i32", prost_path(proto), input), false)
+            }
+            (rust, proto, Direction::FromProto) if self.enums.iter().any(|e| e == rust) => (
+                format!(
+                    "{}::try_from({}).map({}::from).map_err(|_| ConversionError::UnknownEnumValue({}))",
+                    prost_path(proto),
+                    input,
+                    rust,
+                    input
+                ),
+                true,
+            ),
+            // Scalars are used as is when prost uses the same Rust type, and converted
+            // from and to it otherwise, e.g. `u64` to `int64`
+            (rust, proto, _) if prost_scalar(proto) == Some(rust) => (input.to_string(), false),
+            (_, proto, Direction::ToProto) if is_proto_scalar(proto) => (
+                format!("{}::try_from({})", prost_scalar(proto).unwrap(), input),
+                true,
+            ),
+            (_, proto, Direction::ToProto) => {
+                (format!("{}::try_from({})", prost_path(proto), input), true)
+            }
+            (rust, _, Direction::FromProto) => (format!("{}::try_from({})", rust, input), true),
+        }
+    }
+
+    // Expression converting the field `input`, and whether the conversion can fail.
+    pub fn convert_field(
+        &self,
+        field: &Field,
+        input: &str,
+        direction: &Direction,
+    ) -> (String, bool) {
+        let input = input.to_string();
+        let (elem, fallible) = self.convert(&field.rust, &field.proto, "v", direction);
+        let identity = elem == "v";
+        let message = !is_proto_scalar(&field.proto) && !self.enums.contains(&field.rust);
+
+        if let Elements::Map(key_rust, key_proto) = &field.elements {
+            let input = match (field.optional, direction) {
+                (true, Direction::ToProto) => format!("{}.unwrap_or_default()", input),
+                _ => input,
+            };
+            let (key, key_fallible) = self.convert(key_rust, key_proto, "k", direction);
+            // The map types may differ, e.g. `BTreeMap` and prost's `HashMap`
+            let output = if identity && key == "k" {
+                format!("{}.into_iter().collect()", input)
+            } else if fallible || key_fallible {
+                let question = |fallible| if fallible { "?" } else { "" };
+                format!(
+                    "{}.into_iter().map(|(k, v)| Ok(({}{}, {}{}))).collect::<std::result::Result<_, ConversionError>>()?",
+                    input,
+                    key,
+                    question(key_fallible),
+                    elem,
+                    question(fallible)
+                )
+            } else {
+                format!(
+                    "{}.into_iter().map(|(k, v)| ({}, {})).collect()",
+                    input, key, elem
+                )
+            };
+            let fallible = fallible || key_fallible;
+            return match (field.optional, direction) {
+                (true, Direction::FromProto) => (format!("Some({})", output), fallible),
+                _ => (output, fallible),
+            };
+        }
+
+        if let Elements::Repeated = field.elements {
+            let input = match (field.optional, direction) {
+                (true, Direction::ToProto) => format!("{}.unwrap_or_default()", input),
+                _ => input,
+            };
+            let output = if identity {
+                input
+            } else if fallible {
+                format!(
+                    "{}.into_iter().map(|v| {}).collect::<std::result::Result<Vec<_>, _>>()?",
+                    input, elem
+                )
+            } else {
+                format!("{}.into_iter().map(|v| {}).collect()", input, elem)
+            };
+            return match (field.optional, direction) {
+                (true, Direction::FromProto) => (format!("Some({})", output), fallible),
+                _ => (output, fallible),
+            };
+        }
+
+        if field.optional {
+            return if identity {
+                (input, false)
+            } else if fallible {
+                (format!("{}.map(|v| {}).transpose()?", input, elem), true)
+            } else {
+                (format!("{}.map(|v| {})", input, elem), false)
+            };
+        }
+
+        let question = if fallible { "?" } else { "" };
+        match (message, direction) {
+            // Singular message fields are always optional in prost
+            (true, Direction::ToProto) => {
+                let (elem, _) = self.convert(&field.rust, &field.proto, &input, direction);
+                (format!("Some({}{})", elem, question), fallible)
+            }
+            (true, Direction::FromProto) => {
+                let input = format!(
+                    "{}.ok_or(ConversionError::MissingField(\"{}\"))?",
+                    input, field.prost
+                );
+                let (elem, _) = self.convert(&field.rust, &field.proto, &input, direction);
+                (format!("{}{}", elem, question), true)
+            }
+            (false, _) => {
+                let (elem, _) = self.convert(&field.rust, &field.proto, &input, direction);
+                (format!("{}{}", elem, question), fallible)
+            }
+        }
+    }
+
+    fn print_impl(&self, name: &str, fields: &[Field], direction: Direction) -> String {
+        let (source, target) = match direction {
+            Direction::ToProto => (name.to_string(), prost_path(name)),
+            Direction::FromProto => (prost_path(name), name.to_string()),
+        };
+
+        let mut fallible = false;
+        let mut body = String::new();
+        for field in fields {
+            let (input, output) = match direction {
+                Direction::ToProto => (&field.name, &field.prost),
+                Direction::FromProto => (&field.prost, &field.name),
+            };
+            let input = format!("value.{}", input);
+            let (value, field_fallible) = self.convert_field(field, &input, &direction);
+            fallible |= field_fallible;
+            body.push_str(&format!("            {}: {},\n", output, value));
+        }
+
+        let mut out = String::new();
+        if fallible {
+            out.push_str(&format!("impl TryFrom<{}> for {} {{\n", source, target));
+            out.push_str("    type Error = ConversionError;\n\n");
+            out.push_str(&format!(
+                "    fn try_from(value: {}) -> std::result::Result<Self, Self::Error> {{\n",
+                source
+            ));
+            out.push_str("        Ok(Self {\n");
+            out.push_str(&body);
+            out.push_str("        })\n");
+        } else {
+            out.push_str(&format!("impl From<{}> for {} {{\n", source, target));
+            out.push_str(&format!("    fn from(value: {}) -> Self {{\n", source));
+            out.push_str("        Self {\n");
+            out.push_str(&body);
+            out.push_str("        }\n");
+        }
+        out.push_str("    }\n}\n\n");
+        out
+    }
+
+    fn handle_interface(
+        &self,
+        def: &TypeDef,
+        fields: &[ir::Field],
+        rust: &RustBackend,
+        proto: &ProtobufBackend,
+    ) -> String {
+        // Generic types have no proto counterpart
+        if !def.generics.is_empty() {
+            return String::new();
+        }
+
+        // Deprecated fields are deprecated on both sides
+        let allow = allow_deprecated(fields.iter().any(|field| field.doc.deprecated.is_some()));
+        let fields: Vec<Field> = fields
+            .iter()
+            .map(|field| Field::new(field, rust, proto))
+            .collect();
+
+        let mut out = allow.to_string();
+        out.push_str(&self.print_impl(&def.name, &fields, Direction::ToProto));
+        out.push_str(allow);
+        out.push_str(&self.print_impl(&def.name, &fields, Direction::FromProto));
+        out
+    }
+
+    fn handle_enum(&self, name: &str, members: &[ir::Variant]) -> String {
+        let variants: Vec<String> = members
+            .iter()
+            .map(|member| rust_ident(&member.name.to_case(Case::Pascal)))
+            .collect();
+
+        let allow = allow_deprecated(members.iter().any(|member| member.doc.deprecated.is_some()));
+        let mut out = String::new();
+        for (source, target) in [
+            (name.to_string(), prost_path(name)),
+            (prost_path(name), name.to_string()),
+        ] {
+            out.push_str(allow);
+            out.push_str(&format!("impl From<{}> for {} {{\n", source, target));
+            out.push_str(&format!("    fn from(value: {}) -> Self {{\n", source));
+            out.push_str("        match value {\n");
+            for variant in &variants {
+                out.push_str(&format!(
+                    "            {}::{} => {}::{},\n",
+                    source, variant, target, variant
+                ));
+            }
+            out.push_str("        }\n    }\n}\n\n");
+        }
+        out
+    }
+}
+
+// Generate the `From`/`TryFrom` implementations between the serde types generated
+// in Rust mode and the prost types generated from the proto output, along with the
+// types that were left without conversions.
+pub fn handle_conversions(
+    module: &Module,
+    options: ProtoOptions,
+    rust: RustBackend,
+) -> (String, Vec<Unconvertible>) {
+    let proto = ProtobufBackend::new(options.clone());
+    let conversions = Conversions::new(module, &rust, &proto);
+
+    let mut out = String::new();
+    let mut unconvertible = Vec::new();
+    for def in module.types() {
+        match &def.kind {
+            TypeDefKind::Struct(fields) => match conversions.skipped(def, &rust, &proto) {
+                Some((field, kind)) => {
+                    out.push_str(&format!(
+                        "// `{}` has no conversions, as its field `{}` can't be converted\n\n",
+                        def.name, field
+                    ));
+                    unconvertible.push(Unconvertible {
+                        span: def.span,
+                        kind,
+                        emitted: format!("no conversions of `{}`", def.name),
+                    });
+                }
+                None => out.push_str(&conversions.handle_interface(def, fields, &rust, &proto)),
+            },
+            TypeDefKind::Enum(members) => {
+                out.push_str(&conversions.handle_enum(&def.name, members));
+            }
+            _ => (),
+        }
+    }
+    // The helpers are also used by the bridge, to convert the parameters and results
+    // of the rpcs. Decimals are then only named by the helpers.
+    let decimal = conversions.decimal.get();
+    for method in module.services().flat_map(|service| &service.methods) {
+        let (params, result) = rpc_fields(method, &rust, &proto);
+        for param in params
+            .iter()
+            .filter(|param| conversions.unsupported(param).is_none())
+        {
+            conversions.convert_field(param, "value", &Direction::FromProto);
+        }
+        if conversions.unsupported(&result).is_none() {
+            conversions.convert_field(&result, "value", &Direction::ToProto);
+        }
+    }
+
+    let mut header = String::new();
+    let helpers = matches!(options.decimal, DecimalRepr::Message) && conversions.decimal.get();
+    if decimal || helpers {
+        header.push_str("use rust_decimal::Decimal;\n\n");
+    }
+    header.push_str("use super::*;\n\n");
+    header.push_str(&conversion_error(conversions.decimal.get()));
+    header.push('\n');
+    if helpers {
+        header.push_str(DECIMAL_CONVERSIONS);
+        header.push('\n');
+    }
+    if conversions.values.get() {
+        header.push_str(VALUE_CONVERSIONS);
+        header.push('\n');
+    }
+    (format!("{}{}", header, out), unconvertible)
+}
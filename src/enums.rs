@@ -1,17 +1,60 @@
-use convert_case::{Case, Casing};
+use swc_common::Spanned;
 use swc_ecma_ast::{TsEnumDecl, TsEnumMember, TsEnumMemberId};
 
-pub fn handle_enum(TsEnumDecl { id, members, .. }: TsEnumDecl) {
-    println!("#[derive(Debug, Serialize, Deserialize)]");
-    println!("pub enum {} {{", id.sym);
-    for member in members {
-        if let TsEnumMember {
-            id: TsEnumMemberId::Ident(id),
-            ..
-        } = member
-        {
-            println!("\t{}", id.sym.to_string().to_case(Case::Pascal))
-        }
+use crate::ir::{Doc, Literal, TypeDef, TypeDefKind, Variant};
+use crate::utils::{lower_literal, Context};
+
+pub fn handle_enum(
+    TsEnumDecl {
+        id, members, span, ..
+    }: TsEnumDecl,
+    cx: &mut Context,
+) -> TypeDef {
+    let mut variants: Vec<Variant> = Vec::new();
+    // Members without a value are numbered after the previous one, as in TypeScript
+    let mut next = Some(0.0);
+    for TsEnumMember { id, init, span } in members {
+        let value = match init {
+            Some(init) => {
+                let value = lower_literal(&init);
+                if value.is_none() {
+                    cx.warn(
+                        init.span(),
+                        String::from("enum member value is not a literal, using the member name"),
+                    );
+                }
+                value
+            }
+            None => next.map(Literal::Number),
+        };
+        next = match value {
+            Some(Literal::Number(number)) => Some(number + 1.0),
+            _ => None,
+        };
+        variants.push(Variant {
+            name: match id {
+                TsEnumMemberId::Ident(id) => id.sym.to_string(),
+                // Members declared with a string name, such as `'in-progress' = 'in-progress'`
+                TsEnumMemberId::Str(id) => id.value.to_string(),
+            },
+            value,
+            doc: cx.jsdoc(span.lo).doc(),
+        });
+    }
+
+    // Enums are serialized either as strings or as numbers
+    let number = |variant: &Variant| matches!(variant.value, Some(Literal::Number(_)));
+    if variants.iter().any(number) && !variants.iter().all(number) {
+        cx.warn(
+            span,
+            String::from("enum mixes string and number values, its numbers are serialized as the member names"),
+        );
+    }
+    TypeDef {
+        name: id.sym.to_string(),
+        generics: Vec::new(),
+        kind: TypeDefKind::Enum(variants),
+        doc: Doc::default(),
+        span,
     }
-    println!("}}")
 }
@@ -1,128 +1,53 @@
-use std::fmt::Display;
-
 extern crate swc_ecma_parser;
 
+use swc_common::Spanned;
 use swc_ecma_ast::{TsInterfaceDecl, TsTypeParamDecl};
 
-use crate::utils::{ConversionType, Param};
-
-struct Interface {
-    name: String,
-    generics: Vec<String>,
-    properties: Vec<Param>,
-    conversion_type: ConversionType,
-}
-
-impl Interface {
-    fn fmt_rust(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "#[derive(Debug, Serialize, Deserialize)]")?;
-        writeln!(f, "#[serde(rename_all = \"camelCase\")]")?;
-
-        let name = if !self.generics.is_empty() {
-            format!("{}<{}>", self.name, self.generics.join(", "))
-        } else {
-            self.name.clone()
-        };
-        writeln!(f, "pub struct {} {{", name)?;
-
-        for property in &self.properties {
-            writeln!(f, "  {}", property)?;
-        }
-        writeln!(f, "}}")
-    }
-
-    fn fmt_proto(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = if !self.generics.is_empty() {
-            format!("{}<{}>", self.name, self.generics.join(" "))
-        } else {
-            self.name.clone()
-        };
-        writeln!(f, "message {} {{", name)?;
-        let mut property_count = 0;
-        for property in &self.properties {
-            writeln!(f, "  {} = {};", property, property_count)?;
-            property_count += 1;
-        }
-        writeln!(f, "}}")
-    }
-}
-
-impl Display for Interface {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.conversion_type {
-            ConversionType::Rust => self.fmt_rust(f),
-            ConversionType::Protobuf => self.fmt_proto(f),
-        }
-    }
-}
-
-struct MapInterface {
-    prefix: String,
-    name: String,
-    generics: Vec<String>,
-    key: String,
-    val: String,
-}
-
-impl Display for MapInterface {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "#[derive(Debug, Serialize, Deserialize)]")?;
+use crate::ir::{Doc, Field, TypeDef, TypeDefKind};
+use crate::utils::{lower_type_element, Context, Element, Target};
 
-        let name = if !self.generics.is_empty() {
-            format!("{}<{}>", self.name, self.generics.join(", "))
-        } else {
-            self.name.clone()
-        };
-        writeln!(
-            f,
-            "pub struct {}(HashMap<{}, {}>);",
-            name, self.key, self.val
-        )
+pub fn generics(type_params: &Option<TsTypeParamDecl>) -> Vec<String> {
+    match type_params {
+        Some(TsTypeParamDecl { params, .. }) => params
+            .iter()
+            .map(|param| param.name.sym.to_string())
+            .collect(),
+        None => Vec::new(),
     }
 }
 
-pub fn handle_interface(interface: TsInterfaceDecl, conversion_type: ConversionType) {
-    let mut generics: Vec<String> = Vec::new();
-
-    if let Some(TsTypeParamDecl { params, .. }) = &interface.type_params {
-        for param in params {
-            generics.push(param.name.sym.to_string());
-        }
-    }
+pub fn handle_interface(interface: TsInterfaceDecl, cx: &mut Context) -> TypeDef {
+    let name = interface.id.sym.to_string();
+    let generics = generics(&interface.type_params);
 
-    let mut properties: Vec<Param> = Vec::new();
+    let mut properties: Vec<Field> = Vec::new();
     for property in interface.body.body {
-        let (prefix, key, value, optional, map_interface) =
-            conversion_type.extract_key_value_from_ts_type_element(property);
-        if map_interface {
-            println!(
-                "{}",
-                MapInterface {
-                    prefix,
-                    generics: generics.clone(),
-                    name: interface.id.sym.to_string(),
-                    key: key,
-                    val: value
-                }
-            );
-        } else {
-            properties.push(Param {
-                prefix,
-                key,
-                val: value,
-                optional,
-                conversion_type: conversion_type.get(),
-            })
+        let span = property.span();
+        match lower_type_element(property, cx) {
+            // An index signature makes the whole interface a map
+            Some(Element::Map(key, value)) => {
+                return TypeDef {
+                    name,
+                    generics,
+                    kind: TypeDefKind::Map { key, value },
+                    doc: Doc::default(),
+                    span: interface.span,
+                };
+            }
+            Some(Element::Field(mut field)) => {
+                field.doc = cx.jsdoc(span.lo).doc();
+                cx.annotate(span, &format!("{}.{}", name, field.name), Target::Property);
+                properties.push(field);
+            }
+            None => (),
         }
     }
 
-    println!(
-        "{}",
-        Interface {
-            generics,
-            name: interface.id.sym.to_string(),
-            properties,
-            conversion_type,
-        }
-    )
+    TypeDef {
+        name,
+        generics,
+        kind: TypeDefKind::Struct(properties),
+        doc: Doc::default(),
+        span: interface.span,
+    }
 }
@@ -0,0 +1,412 @@
+// Intermediate representation of a TypeScript module. It is lowered once from the
+// swc AST, then rendered by each backend.
+
+use serde::Deserialize;
+use swc_common::Span;
+
+/// Representation of a TypeScript `number`, as chosen by the configuration or a
+/// `@number` JSDoc tag
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub enum NumberType {
+    #[default]
+    U64,
+    I64,
+    U32,
+    I32,
+    F64,
+}
+
+impl NumberType {
+    pub fn rust(&self) -> &'static str {
+        match self {
+            NumberType::U64 => "u64",
+            NumberType::I64 => "i64",
+            NumberType::U32 => "u32",
+            NumberType::I32 => "i32",
+            NumberType::F64 => "f64",
+        }
+    }
+
+    /// `u64` keeps the `int64` it has always been emitted as
+    pub fn proto(&self) -> &'static str {
+        match self {
+            NumberType::U64 | NumberType::I64 => "int64",
+            NumberType::U32 => "uint32",
+            NumberType::I32 => "int32",
+            NumberType::F64 => "double",
+        }
+    }
+}
+
+impl TryFrom<String> for NumberType {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        [
+            NumberType::U64,
+            NumberType::I64,
+            NumberType::U32,
+            NumberType::I32,
+            NumberType::F64,
+        ]
+        .into_iter()
+        .find(|ty| ty.rust() == name)
+        .ok_or_else(|| {
+            format!(
+                "unknown number type `{}`, expected u64, i64, u32, i32 or f64",
+                name
+            )
+        })
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum Primitive {
+    String,
+    // Numbers are lowered without a representation, which the overrides set before
+    // rendering, from the configuration and the `@number` JSDoc tags
+    Number(Option<NumberType>),
+    Boolean,
+    // `any`, `unknown` and the types that have no better representation
+    Any,
+    Object,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum TypeExpr {
+    Primitive(Primitive),
+    Array(Box<TypeExpr>),
+    // Key and value of an index signature or a `Record`
+    Map(Box<TypeExpr>, Box<TypeExpr>),
+    // Type referenced by name, such as `BigNumber`, a declared type or a generic parameter
+    Ref {
+        name: String,
+        args: Vec<TypeExpr>,
+    },
+    Union(Vec<TypeExpr>),
+    Tuple(Vec<TypeExpr>),
+    // Value that may be missing, declared with `?`, a default value or `| undefined`
+    Optional(Box<TypeExpr>),
+    // Type mapped by the configuration to a type written as is in Rust and/or proto,
+    // the backends render the original type otherwise
+    Custom {
+        ty: Box<TypeExpr>,
+        rust: Option<String>,
+        proto: Option<String>,
+    },
+}
+
+impl TypeExpr {
+    pub fn optional(self) -> TypeExpr {
+        match self {
+            TypeExpr::Optional(_) => self,
+            ty => TypeExpr::Optional(Box::new(ty)),
+        }
+    }
+
+    // Build a union, flattening nested unions and removing duplicated members.
+    // A single remaining member is returned as is.
+    pub fn union(members: Vec<TypeExpr>, nullable: bool) -> TypeExpr {
+        let mut flat: Vec<TypeExpr> = Vec::new();
+        let mut nullable = nullable;
+        for member in members {
+            let (member, optional) = member.split_optional();
+            nullable |= optional;
+            let nested = match member {
+                TypeExpr::Union(nested) => nested,
+                member => vec![member],
+            };
+            for member in nested {
+                if !flat.contains(&member) {
+                    flat.push(member);
+                }
+            }
+        }
+
+        let ty = if flat.len() == 1 {
+            flat.remove(0)
+        } else {
+            TypeExpr::Union(flat)
+        };
+        if nullable {
+            ty.optional()
+        } else {
+            ty
+        }
+    }
+
+    // Type without its optional wrapper, and whether it was optional
+    pub fn split_optional(self) -> (TypeExpr, bool) {
+        match self {
+            TypeExpr::Optional(ty) => (*ty, true),
+            ty => (ty, false),
+        }
+    }
+
+    pub fn is_optional(&self) -> bool {
+        matches!(self, TypeExpr::Optional(_))
+    }
+
+    // Represent the numbers of this type that have no representation yet as `number`
+    pub fn resolve_numbers(&mut self, number: NumberType) {
+        self.walk_mut(&mut |ty| {
+            if let TypeExpr::Primitive(Primitive::Number(repr @ None)) = ty {
+                *repr = Some(number);
+            }
+        });
+    }
+
+    // Apply `f` to this type and every type nested in it, innermost first
+    pub fn walk_mut(&mut self, f: &mut dyn FnMut(&mut TypeExpr)) {
+        match self {
+            TypeExpr::Primitive(_) => (),
+            TypeExpr::Array(ty) | TypeExpr::Optional(ty) | TypeExpr::Custom { ty, .. } => {
+                ty.walk_mut(f)
+            }
+            TypeExpr::Map(key, value) => {
+                key.walk_mut(f);
+                value.walk_mut(f);
+            }
+            TypeExpr::Ref { args: types, .. } | TypeExpr::Union(types) | TypeExpr::Tuple(types) => {
+                for ty in types {
+                    ty.walk_mut(f);
+                }
+            }
+        }
+        f(self);
+    }
+}
+
+// Documentation of a declaration or member, taken from its JSDoc comment
+#[derive(Clone, Default)]
+pub struct Doc {
+    // Description, without the tags
+    pub text: String,
+    // Description of the value returned by a method, from `@returns`
+    pub returns: Option<String>,
+    // Note of the `@deprecated` tag, empty if it has none
+    pub deprecated: Option<String>,
+    // JSON payloads of the `@example` tags
+    pub examples: Vec<Example>,
+}
+
+// JSON payload of an `@example` tag
+#[derive(Clone)]
+pub struct Example {
+    // Whether the example is the parameters of a method rather than its result, as
+    // set by a caption such as `<caption>Request</caption>`
+    pub request: bool,
+    pub json: String,
+}
+
+// Literal value written in TypeScript, such as the default value of a parameter
+#[derive(Clone, PartialEq)]
+pub enum Literal {
+    String(String),
+    Number(f64),
+    Boolean(bool),
+}
+
+pub struct Field {
+    // Key of the field in JSON
+    pub name: String,
+    pub ty: TypeExpr,
+    // Name of the field in the generated code when it's renamed by the configuration
+    pub rename: Option<String>,
+    // Number of the field in proto when it's set by the configuration
+    pub tag: Option<u32>,
+    // Default value of a parameter, used when it is left out
+    pub default: Option<Literal>,
+    pub doc: Doc,
+}
+
+impl Field {
+    pub fn new(name: String, ty: TypeExpr) -> Field {
+        Field {
+            name,
+            ty,
+            rename: None,
+            tag: None,
+            default: None,
+            doc: Doc::default(),
+        }
+    }
+
+    // Name the generated field is derived from
+    pub fn code_name(&self) -> &str {
+        self.rename.as_deref().unwrap_or(&self.name)
+    }
+}
+
+// Member of an enum
+pub struct Variant {
+    pub name: String,
+    // Value of the member in JSON, a string or a number, unless it's not a literal
+    pub value: Option<Literal>,
+    pub doc: Doc,
+}
+
+pub enum TypeDefKind {
+    Struct(Vec<Field>),
+    Map { key: TypeExpr, value: TypeExpr },
+    Enum(Vec<Variant>),
+    Alias(TypeExpr),
+}
+
+pub struct TypeDef {
+    pub name: String,
+    pub generics: Vec<String>,
+    pub kind: TypeDefKind,
+    pub doc: Doc,
+    // Declaration in the source, for the diagnostics of the backends
+    pub span: Span,
+}
+
+pub struct Method {
+    pub name: String,
+    // Parameters, documented by the `@param` tags of the method
+    pub params: Vec<Field>,
+    pub returns: TypeExpr,
+    // JSON-RPC command called by the method
+    pub cmd: Option<String>,
+    // Arguments of the call, as paths into the parameters such as `scheme.minColRatio`
+    pub args: Vec<String>,
+    pub doc: Doc,
+    pub span: Span,
+}
+
+pub struct Service {
+    pub name: String,
+    pub methods: Vec<Method>,
+}
+
+pub enum Item {
+    Type(Box<TypeDef>),
+    Service(Service),
+}
+
+#[derive(Default)]
+pub struct Module {
+    pub items: Vec<Item>,
+}
+
+impl Module {
+    pub fn types(&self) -> impl Iterator<Item = &TypeDef> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Type(def) => Some(def.as_ref()),
+            Item::Service(_) => None,
+        })
+    }
+
+    pub fn services(&self) -> impl Iterator<Item = &Service> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Service(service) => Some(service),
+            Item::Type(_) => None,
+        })
+    }
+
+    // Apply `f` to every type used in the module, see `TypeExpr::walk_mut`
+    pub fn walk_types_mut(&mut self, f: &mut dyn FnMut(&mut TypeExpr)) {
+        for item in &mut self.items {
+            match item {
+                Item::Type(def) => match &mut def.kind {
+                    TypeDefKind::Struct(fields) => {
+                        for field in fields {
+                            field.ty.walk_mut(f);
+                        }
+                    }
+                    TypeDefKind::Map { key, value } => {
+                        key.walk_mut(f);
+                        value.walk_mut(f);
+                    }
+                    TypeDefKind::Enum(_) => (),
+                    TypeDefKind::Alias(ty) => ty.walk_mut(f),
+                },
+                Item::Service(service) => {
+                    for method in &mut service.methods {
+                        for param in &mut method.params {
+                            param.ty.walk_mut(f);
+                        }
+                        method.returns.walk_mut(f);
+                    }
+                }
+            }
+        }
+    }
+
+    // Types of the fields, aliases, parameters and results of the module, with the maps
+    // declared as interfaces written as their map type, along with the span of their
+    // declaration or method. The backends look up the imports of a module in them.
+    pub fn field_types(&self) -> Vec<(Span, TypeExpr)> {
+        let mut types = Vec::new();
+        for item in &self.items {
+            match item {
+                Item::Type(def) => match &def.kind {
+                    TypeDefKind::Struct(fields) => {
+                        types.extend(fields.iter().map(|field| (def.span, field.ty.clone())))
+                    }
+                    TypeDefKind::Map { key, value } => types.push((
+                        def.span,
+                        TypeExpr::Map(Box::new(key.clone()), Box::new(value.clone())),
+                    )),
+                    TypeDefKind::Enum(_) => (),
+                    TypeDefKind::Alias(ty) => types.push((def.span, ty.clone())),
+                },
+                Item::Service(service) => {
+                    for method in &service.methods {
+                        types.extend(
+                            method
+                                .params
+                                .iter()
+                                .map(|param| (method.span, param.ty.clone())),
+                        );
+                        types.push((method.span, method.returns.clone()));
+                    }
+                }
+            }
+        }
+        types
+    }
+
+    // Merge the declarations sharing a name, which would otherwise be rendered twice.
+    // Interfaces are merged field by field as TypeScript does, other declarations
+    // keep the first definition.
+    pub fn dedup(&mut self) {
+        let mut items: Vec<Item> = Vec::new();
+        for item in std::mem::take(&mut self.items) {
+            let def = match item {
+                Item::Type(def) => def,
+                service => {
+                    items.push(service);
+                    continue;
+                }
+            };
+            let previous = items.iter_mut().find_map(|item| match item {
+                Item::Type(previous) if previous.name == def.name => Some(previous.as_mut()),
+                _ => None,
+            });
+            match (previous, *def) {
+                (
+                    Some(TypeDef {
+                        kind: TypeDefKind::Struct(fields),
+                        ..
+                    }),
+                    TypeDef {
+                        kind: TypeDefKind::Struct(merged),
+                        ..
+                    },
+                ) => {
+                    for field in merged {
+                        if !fields.iter().any(|f| f.name == field.name) {
+                            fields.push(field);
+                        }
+                    }
+                }
+                (Some(_), _) => (),
+                (None, def) => items.push(Item::Type(Box::new(def))),
+            }
+        }
+        self.items = items;
+    }
+}
@@ -0,0 +1,1156 @@
+mod alias;
+mod backend;
+mod breaking;
+mod bridge;
+mod class;
+mod config;
+mod conversions;
+mod enums;
+mod interface;
+mod ir;
+mod utils;
+
+pub use backend::{DecimalRepr, MapType, RustOptions, Visibility};
+pub use breaking::{check_breaking_changes, BreakingChange};
+pub use config::{Config, Mapping, Numbers, Overrides};
+pub use ir::NumberType;
+
+use alias::handle_alias;
+use backend::{Backend, ProtoOptions, ProtobufBackend, RustBackend};
+use bridge::handle_bridge;
+use class::handle_class;
+use conversions::handle_conversions;
+use enums::handle_enum;
+use interface::handle_interface;
+use ir::Item;
+use utils::{Context, Target};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use convert_case::{Case, Casing};
+
+use swc_common::comments::SingleThreadedComments;
+use swc_common::sync::Lrc;
+use swc_common::{FileName, SourceMap, Span, Spanned};
+
+use swc_ecma_ast::ModuleDecl::ExportDecl;
+use swc_ecma_ast::ModuleItem::ModuleDecl;
+use swc_ecma_ast::{ClassDecl, Decl, Module, ModuleItem};
+
+use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax};
+
+/// Output format of a conversion
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    /// Serde types and JSON-RPC client methods
+    Rust,
+    /// Proto3 messages and gRPC services
+    Proto,
+    /// `From`/`TryFrom` implementations between the serde and prost types
+    Conversions,
+    /// tonic services forwarding to the JSON-RPC client
+    Bridge,
+}
+
+impl Format {
+    // Name of the file generated for `module`
+    fn file_name(&self, module: &str) -> String {
+        match self {
+            Format::Rust => format!("{}.rs", module),
+            Format::Proto => format!("{}.proto", module),
+            Format::Conversions => format!("{}_conversions.rs", module),
+            Format::Bridge => format!("{}_bridge.rs", module),
+        }
+    }
+}
+
+/// Options of a conversion, usually loaded from a [`Config`]
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub format: Format,
+    pub decimal: DecimalRepr,
+    pub rust: RustOptions,
+    pub overrides: Overrides,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            format: Format::Rust,
+            decimal: DecimalRepr::String,
+            rust: RustOptions::default(),
+            overrides: Overrides::default(),
+        }
+    }
+}
+
+/// TypeScript module to convert. The path names the generated module and is
+/// used in diagnostics, it is not read.
+#[derive(Debug)]
+pub struct Source {
+    pub path: String,
+    pub text: String,
+}
+
+/// Generated text of a single module
+#[derive(Debug)]
+pub struct Generated {
+    pub path: String,
+    /// Name of the module, derived from its file name, which is also its proto package
+    pub module: String,
+    /// Name of the file to write the module to, e.g. `loan.proto`
+    pub file_name: String,
+    pub text: String,
+    /// JSON-RPC client methods of a Rust module, generated apart from its types as an
+    /// `impl` of the `Client` that provides `call`. Written after the types, or to
+    /// `<module>_client.rs` by `write_to_dir`.
+    pub client: Option<String>,
+}
+
+/// Result of a successful [`convert`]
+#[derive(Debug)]
+pub struct Output {
+    pub modules: Vec<Generated>,
+    /// Warnings about the constructs that were skipped or stubbed
+    pub diagnostics: Vec<Diagnostic>,
+    pub fallbacks: Vec<Fallback>,
+    pub summary: Summary,
+}
+
+impl Output {
+    /// Write the generated modules one after the other, in the order of the sources
+    pub fn write(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+        for module in &self.modules {
+            writer.write_all(module.text.as_bytes())?;
+            if let Some(client) = &module.client {
+                writer.write_all(client.as_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Write each generated module to its own files in `dir`, returning the written paths.
+    /// Nothing is written if two modules would be written to the same file.
+    pub fn write_to_dir(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let mut names: HashMap<String, &str> = HashMap::new();
+        for module in &self.modules {
+            for (name, _) in module.files() {
+                if let Some(other) = names.insert(name.clone(), &module.path) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("{} and {} both generate {}", other, module.path, name),
+                    ));
+                }
+            }
+        }
+
+        std::fs::create_dir_all(dir)?;
+        let mut files = Vec::new();
+        for module in &self.modules {
+            for (name, text) in module.files() {
+                let file = dir.join(name);
+                std::fs::write(&file, text)?;
+                files.push(file);
+            }
+        }
+        Ok(files)
+    }
+}
+
+impl Generated {
+    /// Names of the files the module is written to by [`Output::write_to_dir`], along
+    /// with their text: the file of the module and the file of its client, if any
+    pub fn files(&self) -> Vec<(String, &str)> {
+        let mut files = vec![(self.file_name.clone(), self.text.as_str())];
+        if let Some(client) = &self.client {
+            files.push((format!("{}_client.rs", self.module), client.as_str()));
+        }
+        files
+    }
+}
+
+/// Severity of a [`Diagnostic`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Problem found in a source, located by its line and column
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Category of the diagnostic: `parse` errors, `unsupported` constructs, import
+    /// `cycle`s between the generated proto files or fields whose names `conflict`
+    pub code: &'static str,
+    pub path: String,
+    /// 1-based line and column of the offending construct
+    pub line: usize,
+    pub column: usize,
+    /// Source line of the offending construct
+    pub snippet: String,
+    pub message: String,
+}
+
+// 1-based line and column of a span, along with its source line
+fn locate(cm: &SourceMap, span: Span) -> (usize, usize, String) {
+    let loc = cm.lookup_char_pos(span.lo);
+    let snippet = loc
+        .file
+        .get_line(loc.line - 1)
+        .map(|line| line.trim_end().to_string())
+        .unwrap_or_default();
+    (loc.line, loc.col_display + 1, snippet)
+}
+
+impl Diagnostic {
+    fn new(
+        cm: &SourceMap,
+        severity: Severity,
+        code: &'static str,
+        path: &str,
+        span: Span,
+        message: String,
+    ) -> Self {
+        let (line, column, snippet) = locate(cm, span);
+        Diagnostic {
+            severity,
+            code,
+            path: path.to_string(),
+            line,
+            column,
+            snippet,
+            message,
+        }
+    }
+}
+
+impl Diagnostic {
+    /// JSON object describing the diagnostic, on a single line
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"type\":\"diagnostic\",\"severity\":\"{}\",\"code\":\"{}\",\"message\":{},\"file\":{},\"line\":{},\"column\":{},\"suggested_output\":null}}",
+            self.severity,
+            self.code,
+            json_string(&self.message),
+            json_string(&self.path),
+            self.line,
+            self.column
+        )
+    }
+}
+
+/// Displayed like compiler diagnostics, with the source line and a caret under the column
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}:{}:{}: {}: {}",
+            self.path, self.line, self.column, self.severity, self.message
+        )?;
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.snippet)?;
+        write!(f, "{} | {}^", gutter, " ".repeat(self.column - 1))
+    }
+}
+
+/// Errors that failed a [`convert`], displayed one after the other
+#[derive(Debug)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in &self.0 {
+            writeln!(f, "{}\n", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+/// Type that has no direct counterpart in the output format, along with what was emitted
+#[derive(Clone, Debug)]
+pub struct Fallback {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    /// Kind of construct, such as `union` or `inline object`
+    pub kind: &'static str,
+    /// Type emitted in its place, such as `serde_json::Value`
+    pub emitted: String,
+}
+
+impl Fallback {
+    /// JSON object describing the fallback as a warning, with the emitted type as its
+    /// suggested output
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"type\":\"diagnostic\",\"severity\":\"warning\",\"code\":\"fallback\",\"message\":{},\"file\":{},\"line\":{},\"column\":{},\"suggested_output\":{}}}",
+            json_string(&format!("{} has no direct counterpart", self.kind)),
+            json_string(&self.path),
+            self.line,
+            self.column,
+            json_string(&self.emitted)
+        )
+    }
+}
+
+/// Fallbacks reported by a [`convert`]
+#[derive(Debug)]
+pub struct Fallbacks(pub Vec<Fallback>);
+
+/// Number of declarations converted from the sources, and of constructs skipped
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Summary {
+    pub interfaces: usize,
+    pub enums: usize,
+    pub aliases: usize,
+    pub methods: usize,
+    pub skipped: usize,
+}
+
+impl Summary {
+    fn add(&mut self, module: &ir::Module) {
+        for def in module.types() {
+            match def.kind {
+                ir::TypeDefKind::Struct(_) | ir::TypeDefKind::Map { .. } => self.interfaces += 1,
+                ir::TypeDefKind::Enum(_) => self.enums += 1,
+                ir::TypeDefKind::Alias(_) => self.aliases += 1,
+            }
+        }
+        for service in module.services() {
+            self.methods += service.methods.len();
+        }
+    }
+
+    /// JSON object of the summary, along with the number of errors, warnings and
+    /// fallbacks that were reported
+    pub fn to_json(&self, errors: usize, warnings: usize, fallbacks: usize) -> String {
+        format!(
+            "{{\"type\":\"summary\",\"interfaces\":{},\"enums\":{},\"aliases\":{},\"methods\":{},\"skipped\":{},\"fallbacks\":{},\"errors\":{},\"warnings\":{}}}",
+            self.interfaces, self.enums, self.aliases, self.methods, self.skipped, fallbacks, errors, warnings
+        )
+    }
+}
+
+// JSON string literal of `s`
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Displayed as a table with one row per fallback
+impl Display for Fallbacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<[String; 3]> = self
+            .0
+            .iter()
+            .map(|fallback| {
+                [
+                    fallback.kind.to_string(),
+                    format!("{}:{}:{}", fallback.path, fallback.line, fallback.column),
+                    fallback.emitted.clone(),
+                ]
+            })
+            .collect();
+        let header = [
+            String::from("construct"),
+            String::from("location"),
+            String::from("emitted"),
+        ];
+        let kind_width = rows.iter().chain([&header]).map(|row| row[0].len()).max();
+        let location_width = rows.iter().chain([&header]).map(|row| row[1].len()).max();
+        for [kind, location, emitted] in [&header].into_iter().chain(&rows) {
+            writeln!(
+                f,
+                "{:kind_width$}  {:location_width$}  {}",
+                kind,
+                location,
+                emitted,
+                kind_width = kind_width.unwrap_or_default(),
+                location_width = location_width.unwrap_or_default(),
+            )?;
+        }
+        writeln!(f, "{} fallback(s)", rows.len())
+    }
+}
+
+// Lower the exported declarations of a module to the intermediate representation
+fn lower(body: Vec<ModuleItem>, cx: &mut Context) -> ir::Module {
+    let mut module = ir::Module::default();
+    for item in body {
+        if let ModuleDecl(ExportDecl(export)) = item {
+            let span = export.span;
+            let mut item = match export.decl {
+                Decl::TsInterface(interface) => {
+                    Item::Type(Box::new(handle_interface(interface, cx)))
+                }
+                Decl::Class(ClassDecl { ident, class, .. }) => {
+                    Item::Service(handle_class(ident, class, cx))
+                }
+                Decl::TsEnum(_enum) => Item::Type(Box::new(handle_enum(_enum, cx))),
+                Decl::TsTypeAlias(alias) => Item::Type(Box::new(handle_alias(alias, cx))),
+                _ => continue,
+            };
+            let (name, target) = match &mut item {
+                Item::Type(def) => {
+                    def.doc = cx.jsdoc(span.lo).doc();
+                    (&def.name, Target::Type)
+                }
+                Item::Service(service) => (&service.name, Target::Class),
+            };
+            cx.annotate(span, name, target);
+            module.items.push(item);
+        }
+    }
+    module.dedup();
+    module
+}
+
+fn transpile(module: &ir::Module, backend: &dyn Backend) -> String {
+    module
+        .items
+        .iter()
+        .map(|item| backend.render_item(item))
+        .collect()
+}
+
+// Render a module along with the header and the footer of its file
+fn render(path: &str, module: &ir::Module, backend: &dyn Backend) -> String {
+    let body = transpile(module, backend);
+    format!(
+        "{}{}{}",
+        backend.header(path, module, &body),
+        body,
+        backend.footer(module)
+    )
+}
+
+// Render a Rust module, with its types and their header and footer apart from the
+// client methods of its classes
+fn render_rust(path: &str, module: &ir::Module, backend: &RustBackend) -> (String, Option<String>) {
+    let (services, types): (Vec<&Item>, Vec<&Item>) = module
+        .items
+        .iter()
+        .partition(|item| matches!(item, Item::Service(_)));
+    let body: String = types.iter().map(|item| backend.render_item(item)).collect();
+    let methods: String = services
+        .iter()
+        .map(|item| backend.render_item(item))
+        .collect();
+    // The imports of the types file, found in every type of the module, are also
+    // those of the client, which is included in a module that uses it
+    let text = format!(
+        "{}{}{}",
+        backend.header(path, module, &body),
+        body,
+        backend.footer(module)
+    );
+    let client = (!services.is_empty()).then(|| render_client(path, &methods));
+    (text, client)
+}
+
+// Client methods of a module, implemented on the `Client` that provides `call`
+fn render_client(path: &str, methods: &str) -> String {
+    let mut out = format!(
+        "// Generated by parser_jellyfish_rust from {}, do not edit.\n\n",
+        path
+    );
+    out.push_str("impl Client {\n");
+    let methods = methods.trim_end();
+    for line in methods.lines() {
+        // Method bodies are indented with a tab, which is kept as spaces in the block
+        let line = match line.strip_prefix('\t') {
+            Some(body) => format!("        {}", body),
+            None if line.is_empty() => String::new(),
+            None => format!("    {}", line),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out.push_str("}\n");
+    out
+}
+
+// Lower TypeScript `text`, along with the overrides set by its JSDoc tags, for the
+// unit tests of the modules working on the IR
+#[cfg(test)]
+fn lower_text(text: &str) -> (ir::Module, Overrides) {
+    let cm: Lrc<SourceMap> = Default::default();
+    let source = Source {
+        path: String::from("test.ts"),
+        text: text.to_string(),
+    };
+    let (module, comments) = parse_module(&cm, &source).expect("invalid TypeScript");
+    let mut cx = Context::new(comments);
+    let module = lower(module.body, &mut cx);
+    (module, cx.overrides)
+}
+
+// Parse a module, along with its comments
+fn parse_module(
+    cm: &Lrc<SourceMap>,
+    source: &Source,
+) -> Result<(Module, SingleThreadedComments), Vec<Diagnostic>> {
+    let fm = cm.new_source_file(FileName::Custom(source.path.clone()), source.text.clone());
+
+    let comments = SingleThreadedComments::default();
+    let lexer = Lexer::new(
+        Syntax::Typescript(Default::default()),
+        Default::default(),
+        StringInput::from(&*fm),
+        Some(&comments),
+    );
+
+    let mut parser = Parser::new_from(lexer);
+    let module = parser.parse_module();
+
+    let mut errors: Vec<Diagnostic> = parser
+        .take_errors()
+        .into_iter()
+        .chain(module.as_ref().err().cloned())
+        .map(|e| {
+            let message = e.kind().msg().to_string();
+            Diagnostic::new(
+                cm,
+                Severity::Error,
+                "parse",
+                &source.path,
+                e.span(),
+                message,
+            )
+        })
+        .collect();
+    match module {
+        Ok(module) if errors.is_empty() => Ok((module, comments)),
+        _ => {
+            errors
+                .dedup_by(|a, b| (a.line, a.column, &a.message) == (b.line, b.column, &b.message));
+            Err(errors)
+        }
+    }
+}
+
+// Names of the types declared in a module
+fn declared_types(module: &ir::Module) -> Vec<String> {
+    module.types().map(|def| def.name.clone()).collect()
+}
+
+// Name of a module, derived from its file name
+fn module_name(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_case(Case::Snake))
+        .unwrap_or_else(|| String::from("module"))
+}
+
+// Cycles in the imports of the proto files generated for `modules` by `backends`,
+// which protoc rejects. Each cycle is reported once, at the first declaration of
+// its first module that uses a type of the next one.
+fn import_cycles(
+    cm: &SourceMap,
+    modules: &[(&Source, ir::Module)],
+    backends: &[ProtobufBackend],
+) -> Vec<Diagnostic> {
+    let files: Vec<String> = modules
+        .iter()
+        .map(|(source, _)| Format::Proto.file_name(&module_name(&source.path)))
+        .collect();
+    // Modules imported by each module, along with the first declaration importing them
+    let imports: Vec<Vec<(usize, Span)>> = modules
+        .iter()
+        .zip(backends)
+        .map(|((_, module), backend)| {
+            let mut imported: Vec<(usize, Span)> = Vec::new();
+            for (span, ty) in module.field_types() {
+                for import in backend.imports(&[ty]) {
+                    let other = files.iter().position(|file| *file == import);
+                    if let Some(other) = other {
+                        if !imported.iter().any(|(i, _)| *i == other) {
+                            imported.push((other, span));
+                        }
+                    }
+                }
+            }
+            imported
+        })
+        .collect();
+
+    // Shortest chain of imports from `from` to `to`, both included
+    let path = |from: usize, to: usize| -> Option<Vec<usize>> {
+        let mut previous: Vec<Option<usize>> = vec![None; modules.len()];
+        let mut queue = std::collections::VecDeque::from([from]);
+        while let Some(module) = queue.pop_front() {
+            if module == to {
+                let mut path = vec![to];
+                while let Some(module) = previous[*path.last().unwrap()] {
+                    path.push(module);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &(next, _) in &imports[module] {
+                if next != from && previous[next].is_none() {
+                    previous[next] = Some(module);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    };
+
+    let mut reported: HashSet<Vec<usize>> = HashSet::new();
+    let mut diagnostics = Vec::new();
+    for (start, imported) in imports.iter().enumerate() {
+        for &(next, span) in imported {
+            let mut cycle = match path(next, start) {
+                Some(path) => path,
+                None => continue,
+            };
+            cycle.insert(0, start);
+            // The same cycle is found from each of its modules
+            let mut key = cycle[..cycle.len() - 1].to_vec();
+            key.sort_unstable();
+            if !reported.insert(key) {
+                continue;
+            }
+            let chain: Vec<&str> = cycle.iter().map(|&i| files[i].as_str()).collect();
+            let message = format!(
+                "proto files import each other ({}), which protoc rejects, move the types \
+                 they share to a module of their own",
+                chain.join(" -> ")
+            );
+            let path = &modules[start].0.path;
+            let code = "cycle";
+            diagnostics.push(Diagnostic::new(
+                cm,
+                Severity::Error,
+                code,
+                path,
+                span,
+                message,
+            ));
+        }
+    }
+    diagnostics
+}
+
+// Fields of the structs of `module` that end up with the same name in the code
+// generated for `format`, such as `aB` and `a_b` in Rust, or `message` and `message_`
+// in proto, or with the same proto field number, which would fail to compile
+fn field_conflicts(
+    cm: &SourceMap,
+    path: &str,
+    module: &ir::Module,
+    format: Format,
+) -> Vec<Diagnostic> {
+    // Name of the fields in each target of the format
+    type Naming = fn(&ir::Field) -> String;
+    let mut namings: Vec<(&str, Naming)> = Vec::new();
+    if let Format::Rust | Format::Conversions | Format::Bridge = format {
+        namings.push(("Rust", backend::field_ident));
+    }
+    if let Format::Proto | Format::Conversions | Format::Bridge = format {
+        namings.push(("proto", ProtobufBackend::field_name));
+    }
+    if let Format::Conversions | Format::Bridge = format {
+        namings.push(("prost", conversions::prost_ident));
+    }
+
+    let mut diagnostics = Vec::new();
+    for def in module.types() {
+        let fields = match &def.kind {
+            ir::TypeDefKind::Struct(fields) => fields,
+            _ => continue,
+        };
+        // Pairs of conflicting fields, along with their name in each target, so that
+        // each pair is reported once
+        let mut conflicts: Vec<((usize, usize), Vec<String>)> = Vec::new();
+        for (target, naming) in &namings {
+            let names: Vec<String> = fields.iter().map(naming).collect();
+            for (i, name) in names.iter().enumerate() {
+                let first = match names[..i].iter().position(|other| other == name) {
+                    Some(first) => first,
+                    None => continue,
+                };
+                let named = format!("`{}` in {}", name, target);
+                match conflicts.iter_mut().find(|(pair, _)| *pair == (first, i)) {
+                    Some((_, named_in)) => named_in.push(named),
+                    None => conflicts.push(((first, i), vec![named])),
+                }
+            }
+        }
+        for ((first, i), mut named_in) in conflicts {
+            let last = named_in.pop().unwrap_or_default();
+            let named_in = match named_in.is_empty() {
+                true => last,
+                false => format!("{} and {}", named_in.join(", "), last),
+            };
+            let message = format!(
+                "fields `{}` and `{}` of `{}` are both named {}, rename one of them with \
+                 `@rename` or in the `[rename]` table of the configuration",
+                fields[first].name, fields[i].name, def.name, named_in
+            );
+            let code = "conflict";
+            diagnostics.push(Diagnostic::new(
+                cm,
+                Severity::Error,
+                code,
+                path,
+                def.span,
+                message,
+            ));
+        }
+
+        // Fields without a number of their own take the free ones
+        if let Format::Proto | Format::Conversions | Format::Bridge = format {
+            for (i, field) in fields.iter().enumerate() {
+                let first = fields[..i]
+                    .iter()
+                    .position(|other| field.tag.is_some() && other.tag == field.tag);
+                let first = match first {
+                    Some(first) => &fields[first],
+                    None => continue,
+                };
+                let message = format!(
+                    "fields `{}` and `{}` of `{}` both have the proto field number {}, change \
+                     one of them with `@proto-field` or in the `[proto_fields]` table of the \
+                     configuration",
+                    first.name,
+                    field.name,
+                    def.name,
+                    field.tag.unwrap_or_default()
+                );
+                let code = "conflict";
+                diagnostics.push(Diagnostic::new(
+                    cm,
+                    Severity::Error,
+                    code,
+                    path,
+                    def.span,
+                    message,
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Convert TypeScript modules to the format selected in `options`, returning the
+/// generated text of each module. Modules converted together can reference each
+/// other's types in proto.
+///
+/// ```no_run
+/// use parser_jellyfish_rust::{convert, Options, Source};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let path = "loan.ts";
+/// let text = std::fs::read_to_string(path)?;
+/// let sources = [Source {
+///     path: path.to_string(),
+///     text,
+/// }];
+/// let output = convert(&sources, &Options::default())?;
+/// output.write_to_dir(std::path::Path::new("src/generated"))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn convert(sources: &[Source], options: &Options) -> Result<Output, Diagnostics> {
+    let cm: Lrc<SourceMap> = Default::default();
+
+    // Fallbacks are rendered with the backend of the selected format, which
+    // doesn't depend on the other modules for the fallback types
+    let fallback_backend: Box<dyn Backend> = match options.format {
+        Format::Proto => Box::new(ProtobufBackend::new(ProtoOptions {
+            decimal: options.decimal,
+            package: String::new(),
+            external_types: Rc::default(),
+            enums: Rc::default(),
+        })),
+        _ => Box::new(RustBackend::new(
+            options.rust.clone(),
+            &ir::Module::default(),
+            Rc::default(),
+        )),
+    };
+
+    let mut diagnostics = Vec::new();
+    let mut fallbacks = Vec::new();
+    let mut summary = Summary::default();
+    // JSDoc tags take precedence over the configuration, and apply to every module as
+    // the configuration does
+    let mut overrides = options.overrides.clone();
+    let mut modules: Vec<(&Source, ir::Module)> = Vec::new();
+    for source in sources {
+        match parse_module(&cm, source) {
+            Ok((module, comments)) => {
+                let mut cx = Context::new(comments);
+                let module = lower(module.body, &mut cx);
+                let warnings = cx.warnings;
+                overrides.extend(cx.overrides);
+                summary.skipped += warnings.skipped.len();
+                modules.push((source, module));
+                diagnostics.extend(warnings.skipped.into_iter().map(|(span, message)| {
+                    let code = "unsupported";
+                    Diagnostic::new(&cm, Severity::Warning, code, &source.path, span, message)
+                }));
+                fallbacks.extend(warnings.fallbacks.into_iter().map(|(span, kind, ty)| {
+                    let (line, column, _) = locate(&cm, span);
+                    Fallback {
+                        path: source.path.clone(),
+                        line,
+                        column,
+                        kind,
+                        emitted: fallback_backend.render_type(&ty),
+                    }
+                }));
+            }
+            Err(errors) => diagnostics.extend(errors),
+        }
+    }
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        return Err(Diagnostics(diagnostics));
+    }
+    for (source, module) in &mut modules {
+        overrides.apply(module);
+        summary.add(module);
+        diagnostics.extend(field_conflicts(&cm, &source.path, module, options.format));
+    }
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        return Err(Diagnostics(diagnostics));
+    }
+
+    let packages: Vec<(String, Vec<String>)> = modules
+        .iter()
+        .map(|(source, module)| (module_name(&source.path), declared_types(module)))
+        .collect();
+    let enums: Rc<HashSet<String>> = Rc::new(
+        modules
+            .iter()
+            .flat_map(|(_, module)| module.types())
+            .filter(|def| matches!(def.kind, ir::TypeDefKind::Enum(_)))
+            .map(|def| def.name.clone())
+            .collect(),
+    );
+
+    // The types declared in the other modules are referenced by package in proto, and
+    // imported from the sibling module in Rust
+    let external_types = |package: &str| {
+        let mut external_types = HashMap::new();
+        if let Format::Proto | Format::Rust = options.format {
+            for (other, types) in packages.iter().filter(|(other, _)| other != package) {
+                for name in types {
+                    external_types
+                        .entry(name.clone())
+                        .or_insert_with(|| other.clone());
+                }
+            }
+        }
+        Rc::new(external_types)
+    };
+    let proto_options = |package: &str| ProtoOptions {
+        decimal: options.decimal,
+        package: package.to_string(),
+        external_types: external_types(package),
+        enums: enums.clone(),
+    };
+    if let Format::Proto = options.format {
+        let backends: Vec<ProtobufBackend> = packages
+            .iter()
+            .map(|(package, _)| ProtobufBackend::new(proto_options(package)))
+            .collect();
+        let cycles = import_cycles(&cm, &modules, &backends);
+        if !cycles.is_empty() {
+            diagnostics.extend(cycles);
+            return Err(Diagnostics(diagnostics));
+        }
+    }
+
+    let modules = modules
+        .into_iter()
+        .map(|(source, module)| {
+            let package = module_name(&source.path);
+            let proto_options = proto_options(&package);
+
+            let mut client = None;
+            let text = match options.format {
+                Format::Rust => {
+                    let backend =
+                        RustBackend::new(options.rust.clone(), &module, external_types(&package));
+                    let (text, methods) = render_rust(&source.path, &module, &backend);
+                    client = methods;
+                    text
+                }
+                Format::Proto => {
+                    render(&source.path, &module, &ProtobufBackend::new(proto_options))
+                }
+                // Generated with both backends, see `Backend`
+                Format::Conversions | Format::Bridge => {
+                    let rust = RustBackend::new(options.rust.clone(), &module, Rc::default());
+                    let (text, unconvertible) = match options.format {
+                        Format::Bridge => handle_bridge(&module, proto_options, rust),
+                        _ => handle_conversions(&module, proto_options, rust),
+                    };
+                    // Types left without conversions, and the rpcs left unimplemented
+                    // as a result, are reported as fallbacks
+                    fallbacks.extend(unconvertible.into_iter().map(|unconvertible| {
+                        let (line, column, _) = locate(&cm, unconvertible.span);
+                        Fallback {
+                            path: source.path.clone(),
+                            line,
+                            column,
+                            kind: unconvertible.kind,
+                            emitted: unconvertible.emitted,
+                        }
+                    }));
+                    text
+                }
+            };
+            Generated {
+                path: source.path.clone(),
+                file_name: options.format.file_name(&package),
+                module: package,
+                text,
+                client,
+            }
+        })
+        .collect();
+
+    Ok(Output {
+        modules,
+        diagnostics,
+        fallbacks,
+        summary,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(path: &str, text: &str) -> Source {
+        Source {
+            path: path.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    fn proto() -> Options {
+        Options {
+            format: Format::Proto,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn import_cycle() {
+        let sources = [
+            source(
+                "loan.ts",
+                "export interface Loan {\n  vault: VaultInfo\n}\n",
+            ),
+            source(
+                "vault_info.ts",
+                "export interface VaultInfo {\n  loan: Loan\n}\n",
+            ),
+        ];
+        let Diagnostics(diagnostics) = convert(&sources, &proto()).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "cycle");
+        assert_eq!(
+            (diagnostics[0].path.as_str(), diagnostics[0].line),
+            ("loan.ts", 1)
+        );
+        assert!(diagnostics[0]
+            .message
+            .contains("loan.proto -> vault_info.proto -> loan.proto"));
+    }
+
+    #[test]
+    fn imports_without_cycle() {
+        let sources = [
+            source("loan.ts", "export interface Loan {\n  id: string\n}\n"),
+            source(
+                "vault_info.ts",
+                "export interface VaultInfo {\n  loan: Loan\n}\n",
+            ),
+        ];
+        let output = convert(&sources, &proto()).unwrap();
+        assert!(output.modules[1].text.contains("import \"loan.proto\";"));
+    }
+
+    #[test]
+    fn fallbacks_table() {
+        let fallback = |path: &str, line, kind, emitted: &str| Fallback {
+            path: path.to_string(),
+            line,
+            column: 11,
+            kind,
+            emitted: emitted.to_string(),
+        };
+        let fallbacks = Fallbacks(vec![
+            fallback("vault.ts", 3, "union", "serde_json::Value"),
+            fallback("loan_scheme.ts", 12, "inline object", "serde_json::Value"),
+        ]);
+        assert_eq!(
+            fallbacks.to_string(),
+            "construct      location              emitted\n\
+             union          vault.ts:3:11         serde_json::Value\n\
+             inline object  loan_scheme.ts:12:11  serde_json::Value\n\
+             2 fallback(s)\n"
+        );
+    }
+
+    #[test]
+    fn json_messages() {
+        let diagnostic = Diagnostic {
+            severity: Severity::Warning,
+            code: "unsupported",
+            path: String::from("vault.ts"),
+            line: 4,
+            column: 3,
+            snippet: String::from("  close(): void"),
+            message: String::from("method signature is not supported, skipped"),
+        };
+        assert_eq!(
+            diagnostic.to_json(),
+            "{\"type\":\"diagnostic\",\"severity\":\"warning\",\"code\":\"unsupported\",\
+             \"message\":\"method signature is not supported, skipped\",\"file\":\"vault.ts\",\
+             \"line\":4,\"column\":3,\"suggested_output\":null}"
+        );
+        let fallback = Fallback {
+            path: String::from("vault.ts"),
+            line: 3,
+            column: 11,
+            kind: "union",
+            emitted: String::from("serde_json::Value"),
+        };
+        assert_eq!(
+            fallback.to_json(),
+            "{\"type\":\"diagnostic\",\"severity\":\"warning\",\"code\":\"fallback\",\
+             \"message\":\"union has no direct counterpart\",\"file\":\"vault.ts\",\"line\":3,\
+             \"column\":11,\"suggested_output\":\"serde_json::Value\"}"
+        );
+        let summary = Summary {
+            interfaces: 2,
+            enums: 1,
+            aliases: 0,
+            methods: 3,
+            skipped: 1,
+        };
+        assert_eq!(
+            summary.to_json(0, 2, 1),
+            "{\"type\":\"summary\",\"interfaces\":2,\"enums\":1,\"aliases\":0,\"methods\":3,\
+             \"skipped\":1,\"fallbacks\":1,\"errors\":0,\"warnings\":2}"
+        );
+    }
+
+    #[test]
+    fn json_strings() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(
+            json_string("a \"quoted\" C:\\path\nand\ttab\u{1}"),
+            "\"a \\\"quoted\\\" C:\\\\path\\nand\\ttab\\u0001\""
+        );
+    }
+
+    #[test]
+    fn field_name_conflicts() {
+        let text = "export interface Vault {\n  minColRatio: number\n  min_col_ratio: number\n  \
+                    message: string\n  message_: string\n}\n";
+        let sources = [source("vault.ts", text)];
+        let options = Options {
+            format: Format::Conversions,
+            ..Options::default()
+        };
+        let Diagnostics(diagnostics) = convert(&sources, &options).unwrap_err();
+        let messages: Vec<&str> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.code == "conflict"));
+        assert!(messages[0].starts_with(
+            "fields `minColRatio` and `min_col_ratio` of `Vault` are both named \
+             `min_col_ratio` in Rust, `min_col_ratio` in proto and `min_col_ratio` in prost,"
+        ));
+        assert!(messages[1].starts_with(
+            "fields `message` and `message_` of `Vault` are both named `message` in Rust, \
+             `message_` in proto and `message` in prost,"
+        ));
+
+        // Each format reports the conflicts of its own names
+        let text = "export interface Vault {\n  message: string\n  message_: string\n}\n";
+        let Diagnostics(diagnostics) = convert(&[source("vault.ts", text)], &proto()).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0]
+            .message
+            .contains("both named `message_` in proto,"));
+    }
+
+    #[test]
+    fn proto_field_number_conflicts() {
+        let text = "export interface Vault {\n  /** @proto-field 2 */\n  id: string\n  \
+                    owner: string\n  /** @proto-field 2 */\n  state: string\n}\n";
+        let Diagnostics(diagnostics) = convert(&[source("vault.ts", text)], &proto()).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "conflict");
+        assert!(diagnostics[0]
+            .message
+            .starts_with("fields `id` and `state` of `Vault` both have the proto field number 2,"));
+        // Numbers only matter in proto
+        assert!(convert(&[source("vault.ts", text)], &Options::default()).is_ok());
+
+        // Fields without a number take the free ones
+        let text = text.replace(
+            "/** @proto-field 2 */\n  state",
+            "/** @proto-field 1 */\n  state",
+        );
+        let output = convert(&[source("vault.ts", &text)], &proto()).unwrap();
+        let fields = "  string id = 2;\n  string owner = 3;\n  string state = 1;\n";
+        assert!(output.modules[0].text.contains(fields));
+    }
+
+    #[test]
+    fn renamed_field_name_conflict() {
+        let text = "export interface Vault {\n  minColRatio: number\n  \
+                    /** @rename minimumColRatio */\n  min_col_ratio: number\n}\n";
+        let output = convert(&[source("vault.ts", text)], &Options::default()).unwrap();
+        assert!(output.modules[0].text.contains("minimum_col_ratio: u64,"));
+    }
+}
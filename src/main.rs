@@ -1,89 +1,501 @@
-#![feature(box_patterns)]
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
 
-mod class;
-mod enums;
-mod interface;
-mod utils;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
-use class::handle_class;
-use enums::handle_enum;
-use interface::handle_interface;
-use utils::ConversionType;
+use parser_jellyfish_rust::{
+    check_breaking_changes, convert, Config, DecimalRepr, Diagnostics, Fallbacks, Format, Options,
+    Output, Severity, Source, Summary, Visibility,
+};
 
-use std::path::Path;
+/// Convert the TypeScript declarations of jellyfish to Rust serde types, proto3 schemas and the
+/// gRPC glue between them
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-use swc_common::sync::Lrc;
-use swc_common::{
-    errors::{ColorConfig, Handler},
-    SourceMap,
-};
+#[derive(Subcommand)]
+enum Command {
+    /// Generate serde types and JSON-RPC client methods
+    Rust {
+        #[command(flatten)]
+        common: Common,
+        #[command(flatten)]
+        rust: RustArgs,
+    },
+    /// Generate proto3 messages and gRPC services
+    Proto {
+        #[command(flatten)]
+        common: Common,
+        #[command(flatten)]
+        decimal: DecimalArgs,
+    },
+    /// Generate `From`/`TryFrom` implementations between the serde and prost types
+    Conversions {
+        #[command(flatten)]
+        common: Common,
+        #[command(flatten)]
+        decimal: DecimalArgs,
+    },
+    /// Generate tonic services forwarding every rpc to the JSON-RPC client
+    Bridge {
+        #[command(flatten)]
+        common: Common,
+        #[command(flatten)]
+        decimal: DecimalArgs,
+    },
+    /// Generate several formats in a single run
+    Generate {
+        /// Formats to generate, in order
+        #[arg(short, long = "format", value_delimiter = ',', required = true)]
+        formats: Vec<FormatArg>,
+        #[command(flatten)]
+        common: Common,
+        #[command(flatten)]
+        rust: RustArgs,
+        #[command(flatten)]
+        decimal: DecimalArgs,
+    },
+    /// Compare the generated proto with a previous schema and report the breaking changes
+    Check {
+        /// Previously generated `.proto` file, or directory of `<module>.proto` files
+        #[arg(long, value_name = "PATH")]
+        previous: PathBuf,
+        #[command(flatten)]
+        inputs: Inputs,
+        #[command(flatten)]
+        reporting: Reporting,
+        #[command(flatten)]
+        decimal: DecimalArgs,
+    },
+}
+
+#[derive(Args)]
+struct Inputs {
+    /// TypeScript file to convert, can be repeated
+    #[arg(short, long = "input", value_name = "PATH")]
+    inputs: Vec<PathBuf>,
+    /// TypeScript files to convert
+    #[arg(value_name = "INPUT")]
+    positional: Vec<PathBuf>,
+    /// TOML configuration file with type mappings, renames, skipped declarations and derives
+    #[arg(short, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct Common {
+    #[command(flatten)]
+    inputs: Inputs,
+    /// Write every generated module to a single file instead of stdout
+    #[arg(short, long, value_name = "FILE", conflicts_with = "out_dir")]
+    out: Option<PathBuf>,
+    /// Write each generated module to its own file in a directory
+    #[arg(short = 'd', long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+    #[command(flatten)]
+    reporting: Reporting,
+}
+
+#[derive(Args)]
+struct Reporting {
+    /// Exit with a non-zero code if anything was skipped or fell back to a generic type
+    #[arg(long)]
+    strict: bool,
+    /// Format of the diagnostics written to stderr
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+    /// Also report the number of converted declarations and the written files
+    #[arg(short, long, conflicts_with = "quiet")]
+    verbose: bool,
+    /// Only report errors
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(Args)]
+struct RustArgs {
+    /// Visibility of the fields of the generated structs
+    #[arg(long, value_enum, default_value_t = VisibilityArg::Private)]
+    visibility: VisibilityArg,
+    /// Traits to derive on every generated type, in addition to `Debug`, `Serialize` and
+    /// `Deserialize`
+    #[arg(long, value_delimiter = ',', value_name = "TRAITS")]
+    derive: Vec<String>,
+    /// Derive the standard traits implemented by the types of the fields of each type
+    #[arg(long)]
+    infer_derives: bool,
+}
+
+#[derive(Args)]
+struct DecimalArgs {
+    /// Representation of `BigNumber` in proto
+    #[arg(long, value_enum, default_value_t = DecimalArg::String)]
+    decimal: DecimalArg,
+}
 
-use swc_ecma_ast::ModuleDecl::ExportDecl;
-use swc_ecma_ast::ModuleItem::ModuleDecl;
-use swc_ecma_ast::{ClassDecl, Decl, ModuleItem};
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Rust,
+    Proto,
+    Conversions,
+    Bridge,
+}
 
-use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax};
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum MessageFormat {
+    Human,
+    Json,
+}
 
-fn transpile(body: Vec<ModuleItem>, con_type: ConversionType) {
-    for item in body {
-        let conversion_type = match con_type {
-            ConversionType::Rust => ConversionType::Rust,
-            ConversionType::Protobuf => ConversionType::Protobuf,
+#[derive(Clone, Copy, ValueEnum)]
+enum VisibilityArg {
+    Private,
+    Crate,
+    Pub,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DecimalArg {
+    /// A decimal string
+    String,
+    /// A generated `Decimal` message
+    Message,
+}
+
+impl From<FormatArg> for Format {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Rust => Format::Rust,
+            FormatArg::Proto => Format::Proto,
+            FormatArg::Conversions => Format::Conversions,
+            FormatArg::Bridge => Format::Bridge,
+        }
+    }
+}
+
+impl RustArgs {
+    fn apply(self, options: &mut Options) {
+        options.rust.visibility = match self.visibility {
+            VisibilityArg::Private => Visibility::Private,
+            VisibilityArg::Crate => Visibility::Crate,
+            VisibilityArg::Pub => Visibility::Public,
         };
-        if let ModuleDecl(ExportDecl(export)) = item {
-            match export.decl {
-                Decl::TsInterface(interface) => {
-                    handle_interface(interface, conversion_type);
-                }
-                Decl::Class(ClassDecl { class, .. }) => {
-                    handle_class(class, conversion_type);
-                }
-                Decl::TsEnum(_enum) => {
-                    handle_enum(_enum);
-                }
-                _ => (),
+        options.rust.derives.extend(
+            self.derive
+                .into_iter()
+                .map(|derive| derive.trim().to_string())
+                .filter(|derive| !derive.is_empty()),
+        );
+        options.rust.infer_derives = self.infer_derives;
+    }
+}
+
+impl DecimalArgs {
+    fn apply(self, options: &mut Options) {
+        options.decimal = match self.decimal {
+            DecimalArg::String => DecimalRepr::String,
+            DecimalArg::Message => DecimalRepr::Message,
+        };
+    }
+}
+
+// What a run converts, and where its output goes
+struct Run {
+    inputs: Inputs,
+    formats: Vec<Format>,
+    options: Options,
+    out: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    reporting: Reporting,
+    // Previous proto to check the generated one against, instead of writing it
+    previous: Option<PathBuf>,
+}
+
+impl From<Command> for Run {
+    fn from(command: Command) -> Self {
+        let mut options = Options::default();
+        let (formats, common) = match command {
+            Command::Rust { common, rust } => {
+                rust.apply(&mut options);
+                (vec![Format::Rust], common)
+            }
+            Command::Proto { common, decimal } => {
+                decimal.apply(&mut options);
+                (vec![Format::Proto], common)
+            }
+            Command::Conversions { common, decimal } => {
+                decimal.apply(&mut options);
+                (vec![Format::Conversions], common)
             }
+            Command::Bridge { common, decimal } => {
+                decimal.apply(&mut options);
+                (vec![Format::Bridge], common)
+            }
+            Command::Generate {
+                formats,
+                common,
+                rust,
+                decimal,
+            } => {
+                rust.apply(&mut options);
+                decimal.apply(&mut options);
+                (formats.into_iter().map(Format::from).collect(), common)
+            }
+            Command::Check {
+                previous,
+                inputs,
+                reporting,
+                decimal,
+            } => {
+                decimal.apply(&mut options);
+                return Run {
+                    inputs,
+                    formats: vec![Format::Proto],
+                    options,
+                    out: None,
+                    out_dir: None,
+                    reporting,
+                    previous: Some(previous),
+                };
+            }
+        };
+        Run {
+            inputs: common.inputs,
+            formats,
+            options,
+            out: common.out,
+            out_dir: common.out_dir,
+            reporting: common.reporting,
+            previous: None,
         }
     }
 }
 
+// Report an error that prevents the run from completing, and exit
+fn fail(message: String) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1);
+}
+
 fn main() {
-    let cm: Lrc<SourceMap> = Default::default();
-    let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(cm.clone()));
-
-    let args: Vec<String> = std::env::args().collect();
-    // println!("input file: {}", &args[1]);
-    // println!("conversion type: {}", &args[2]);
-
-    let arg = args[1].as_str();
-    let conversion_type = match args[2].as_str() {
-        "proto" => ConversionType::Protobuf,
-        _ => ConversionType::Rust,
-    };
-    let fm = cm
-        .load_file(Path::new(&arg))
-        .unwrap_or_else(|_| panic!("failed to load {}", &arg));
-
-    let lexer = Lexer::new(
-        Syntax::Typescript(Default::default()),
-        Default::default(),
-        StringInput::from(&*fm),
-        None,
-    );
-
-    let mut parser = Parser::new_from(lexer);
-
-    for e in parser.take_errors() {
-        e.into_diagnostic(&handler).emit();
-    }
-
-    let module = parser
-        .parse_module()
-        .map_err(|e| {
-            // Unrecoverable fatal error occurred
-            e.into_diagnostic(&handler).emit();
+    let Run {
+        inputs,
+        formats,
+        mut options,
+        out,
+        out_dir,
+        reporting,
+        previous,
+    } = Cli::parse().command.into();
+    let json = reporting.message_format == MessageFormat::Json;
+
+    if let Some(path) = &inputs.config {
+        let text = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| fail(format!("failed to read {}: {}", path.display(), e)));
+        Config::from_toml(&text)
+            .unwrap_or_else(|e| fail(format!("invalid configuration {}: {}", path.display(), e)))
+            .apply(&mut options);
+    }
+
+    let paths: Vec<PathBuf> = inputs.inputs.into_iter().chain(inputs.positional).collect();
+    if paths.is_empty() {
+        fail(String::from(
+            "no input, pass TypeScript files as arguments or with --input",
+        ));
+    }
+    // A single previous proto can only be compared with a single module
+    if let Some(previous) = &previous {
+        if paths.len() > 1 && !previous.is_dir() {
+            fail(format!(
+                "{} is a single proto file but {} modules are checked, pass a directory of \
+                 `<module>.proto` files to --previous",
+                previous.display(),
+                paths.len()
+            ));
+        }
+    }
+    // A single file or stdout can only hold a single module in a single format, as the
+    // file of each module has a header of its own. The types and the client methods of
+    // a Rust module are written one after the other.
+    if previous.is_none() && out_dir.is_none() && (paths.len() > 1 || formats.len() > 1) {
+        fail(format!(
+            "{} can only hold a single module in a single format, pass --out-dir to write several",
+            if out.is_some() { "--out" } else { "stdout" }
+        ));
+    }
+    let sources: Vec<Source> = paths
+        .into_iter()
+        .map(|path| {
+            let text = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| fail(format!("failed to read {}: {}", path.display(), e)));
+            Source {
+                path: path.to_string_lossy().into_owned(),
+                text,
+            }
+        })
+        .collect();
+
+    let outputs: Vec<Output> = formats
+        .into_iter()
+        .map(|format| {
+            options.format = format;
+            match convert(&sources, &options) {
+                Ok(output) => output,
+                Err(diagnostics) => {
+                    if json {
+                        for diagnostic in &diagnostics.0 {
+                            eprintln!("{}", diagnostic.to_json());
+                        }
+                        let errors = diagnostics
+                            .0
+                            .iter()
+                            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                            .count();
+                        let warnings = diagnostics.0.len() - errors;
+                        eprintln!("{}", Summary::default().to_json(errors, warnings, 0));
+                    } else {
+                        eprint!("{}", diagnostics);
+                    }
+                    std::process::exit(1);
+                }
+            }
         })
-        .expect("failed to parser module");
+        .collect();
 
-    transpile(module.body, conversion_type)
+    // Every format is converted from the same sources, so their warnings are the same.
+    // The fallbacks are listed for each format since what they emit differs.
+    // With `--message-format json`, every diagnostic and fallback is written as a JSON
+    // object on its own line, followed by a summary object.
+    if let Some(output) = outputs.first() {
+        if json {
+            for diagnostic in &output.diagnostics {
+                eprintln!("{}", diagnostic.to_json());
+            }
+        } else if !reporting.quiet {
+            let warnings = Diagnostics(output.diagnostics.clone());
+            eprint!("{}", warnings);
+        }
+    }
+    let mut fallbacks = 0;
+    for output in &outputs {
+        if json {
+            for fallback in &output.fallbacks {
+                eprintln!("{}", fallback.to_json());
+            }
+        } else if !reporting.quiet && !output.fallbacks.is_empty() {
+            eprint!("{}", Fallbacks(output.fallbacks.clone()));
+        }
+        fallbacks += output.fallbacks.len();
+    }
+    let fallback = fallbacks > 0;
+    if let Some(output) = outputs.first() {
+        let summary = output.summary;
+        if json {
+            let warnings = output.diagnostics.len() + fallbacks;
+            eprintln!("{}", summary.to_json(0, warnings, fallbacks));
+        } else if reporting.verbose {
+            eprintln!(
+                "converted {} interfaces, {} enums, {} aliases and {} methods, skipped {} constructs",
+                summary.interfaces, summary.enums, summary.aliases, summary.methods, summary.skipped
+            );
+        }
+    }
+    // In strict mode, anything that couldn't be converted as written is an error
+    let skipped = outputs
+        .first()
+        .is_some_and(|output| !output.diagnostics.is_empty());
+    if reporting.strict && (fallback || skipped) {
+        std::process::exit(1);
+    }
+
+    // In check mode, nothing is written and the proto output is compared with the
+    // previously generated proto, which is looked up by package in a directory.
+    // Modules without a previous proto are new, so they can't break anything.
+    if let Some(previous) = &previous {
+        let mut breaking = false;
+        for output in &outputs {
+            for module in &output.modules {
+                let file = if previous.is_dir() {
+                    previous.join(&module.file_name)
+                } else {
+                    previous.to_path_buf()
+                };
+                if previous.is_dir() && !file.exists() {
+                    if !reporting.quiet {
+                        eprintln!(
+                            "note: {} has no previous proto in {}, skipped",
+                            module.path,
+                            previous.display()
+                        );
+                    }
+                    continue;
+                }
+                let previous_out = std::fs::read_to_string(&file)
+                    .unwrap_or_else(|e| fail(format!("failed to read {}: {}", file.display(), e)));
+                for change in check_breaking_changes(&previous_out, &module.text) {
+                    eprintln!("{}: {}", file.display(), change);
+                    breaking = true;
+                }
+            }
+        }
+        if breaking {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(dir) = &out_dir {
+        // Nothing is written if two modules, e.g. from sources sharing a file name, would
+        // be written to the same file
+        let mut names: HashMap<String, &str> = HashMap::new();
+        for module in outputs.iter().flat_map(|output| &output.modules) {
+            for (name, _) in module.files() {
+                if let Some(other) = names.insert(name.clone(), &module.path) {
+                    fail(format!(
+                        "{} and {} would both be written to {}, rename one of them",
+                        other,
+                        module.path,
+                        dir.join(name).display()
+                    ));
+                }
+            }
+        }
+        for output in &outputs {
+            let files = output
+                .write_to_dir(dir)
+                .unwrap_or_else(|e| fail(format!("failed to write to {}: {}", dir.display(), e)));
+            if reporting.verbose {
+                for file in files {
+                    eprintln!("wrote {}", file.display());
+                }
+            }
+        }
+        return;
+    }
+
+    if let Some(out) = &out {
+        let mut file = File::create(out)
+            .unwrap_or_else(|e| fail(format!("failed to create {}: {}", out.display(), e)));
+        for output in &outputs {
+            output
+                .write(&mut file)
+                .unwrap_or_else(|e| fail(format!("failed to write {}: {}", out.display(), e)));
+        }
+        if reporting.verbose {
+            eprintln!("wrote {}", out.display());
+        }
+        return;
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    for output in &outputs {
+        output
+            .write(&mut stdout)
+            .unwrap_or_else(|e| fail(format!("failed to write to stdout: {}", e)));
+    }
 }
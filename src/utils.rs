@@ -1,216 +1,593 @@
-use convert_case::{Case, Casing};
-use std::fmt::Display;
+use swc_common::comments::{CommentKind, Comments, SingleThreadedComments};
+use swc_common::{BytePos, Span, Spanned};
 use swc_ecma_ast::{
-    BindingIdent, Expr, TsArrayType, TsEntityName, TsFnParam, TsIndexSignature, TsKeywordType,
-    TsKeywordTypeKind, TsPropertySignature, TsType, TsTypeAnn, TsTypeElement, TsTypeRef,
+    BindingIdent, Expr, Lit, TsArrayType, TsEntityName, TsFnParam, TsIndexSignature, TsKeywordType,
+    TsKeywordTypeKind, TsLit, TsLitType, TsParenthesizedType, TsPropertySignature, TsTupleElement,
+    TsTupleType, TsType, TsTypeElement, TsTypeLit, TsTypeParamInstantiation, TsTypeRef,
+    TsUnionOrIntersectionType, TsUnionType, UnaryExpr, UnaryOp,
 };
 
-pub enum ConversionType {
-    Rust,
-    Protobuf,
+use crate::config::{Mapping, Overrides};
+use crate::ir::{Doc, Example, Field, Literal, NumberType, Primitive, TypeExpr};
+
+// Constructs that couldn't be lowered as written, reported with their span
+#[derive(Default)]
+pub struct Warnings {
+    // Constructs that were skipped or stubbed
+    pub skipped: Vec<(Span, String)>,
+    // Types that have no direct counterpart, with the kind of construct and the
+    // type they fell back to
+    pub fallbacks: Vec<(Span, &'static str, TypeExpr)>,
 }
 
-impl ConversionType {
-    pub fn get(&self) -> ConversionType {
-        match self {
-            ConversionType::Protobuf => ConversionType::Protobuf,
-            ConversionType::Rust => ConversionType::Rust,
-        }
+impl Warnings {
+    pub fn warn(&mut self, span: Span, message: String) {
+        self.skipped.push((span, message));
     }
-    fn map_type_rust(kind: TsKeywordTypeKind) -> String {
-        match kind {
-            TsKeywordTypeKind::TsStringKeyword => String::from("String"),
-            TsKeywordTypeKind::TsNumberKeyword => String::from("u64"),
-            TsKeywordTypeKind::TsBooleanKeyword => String::from("bool"),
-            _ => panic!("MISSING KIND"),
+
+    pub fn fallback(&mut self, span: Span, kind: &'static str, ty: TypeExpr) -> TypeExpr {
+        self.fallbacks.push((span, kind, ty.clone()));
+        ty
+    }
+}
+
+// JSDoc comment of a declaration or member
+#[derive(Default)]
+pub struct JsDoc {
+    // Description, before the first tag
+    pub text: String,
+    // Name of each tag and the text following it, up to the next tag,
+    // e.g. `("number", "f64")` for `@number f64`
+    pub tags: Vec<(String, String)>,
+}
+
+impl JsDoc {
+    // Parse the text of a `/** */` comment, without its delimiters
+    fn parse(comment: &str) -> JsDoc {
+        let mut jsdoc = JsDoc::default();
+        let mut text: Vec<&str> = Vec::new();
+        for line in comment.lines() {
+            // Lines start with a `*`, the space after it is not part of the text
+            let line = line.trim_start();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            let line = line.strip_prefix(' ').unwrap_or(line).trim_end();
+            if let Some(tag) = line.trim_start().strip_prefix('@') {
+                let (name, value) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+                jsdoc
+                    .tags
+                    .push((name.to_string(), value.trim().to_string()));
+            } else if let Some((_, value)) = jsdoc.tags.last_mut() {
+                value.push('\n');
+                value.push_str(line);
+            } else {
+                text.push(line);
+            }
+        }
+        jsdoc.text = text.join("\n").trim().to_string();
+        for (_, value) in &mut jsdoc.tags {
+            *value = value.trim_start_matches('\n').trim_end().to_string();
         }
+        jsdoc
     }
-    fn map_type_proto(kind: TsKeywordTypeKind) -> String {
-        match kind {
-            TsKeywordTypeKind::TsStringKeyword => String::from("string"),
-            TsKeywordTypeKind::TsNumberKeyword => String::from("int64"),
-            TsKeywordTypeKind::TsBooleanKeyword => String::from("bool"),
-            _ => panic!("MISSING KIND"),
+
+    // Values of the tags named `name`
+    pub fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.tags
+            .iter()
+            .filter(move |(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Documentation carried to the generated code
+    pub fn doc(&self) -> Doc {
+        Doc {
+            text: self.text.clone(),
+            returns: self
+                .values("returns")
+                .chain(self.values("return"))
+                .next()
+                .map(|value| description(untyped(value))),
+            deprecated: self.values("deprecated").next().map(description),
+            examples: self.values("example").filter_map(example).collect(),
         }
     }
 
-    pub fn map_type(&self) -> fn(TsKeywordTypeKind) -> String {
+    // Description of the parameter `name`, from a tag such as
+    // `@param {string} name - description`
+    pub fn param(&self, name: &str) -> Option<String> {
+        self.values("param").find_map(|value| {
+            let value = untyped(value);
+            let (param, rest) = value.split_once(char::is_whitespace).unwrap_or((value, ""));
+            // Optional parameters are written `[name]` or `[name=default]`
+            let param = param.trim_start_matches('[').trim_end_matches(']');
+            let param = param.split('=').next().unwrap_or_default();
+            (param == name).then(|| {
+                let rest = rest.trim_start();
+                description(rest.strip_prefix('-').unwrap_or(rest))
+            })
+        })
+    }
+}
+
+// Value of a JSDoc tag without the type it starts with, such as `{string}`
+fn untyped(value: &str) -> &str {
+    match value.strip_prefix('{') {
+        Some(typed) => typed
+            .split_once('}')
+            .map_or("", |(_, rest)| rest)
+            .trim_start(),
+        None => value,
+    }
+}
+
+// Description in the value of a tag, without the indentation of its lines
+fn description(value: &str) -> String {
+    let lines: Vec<&str> = value.lines().map(str::trim).collect();
+    lines.join("\n").trim().to_string()
+}
+
+// JSON payload of an `@example` tag, optionally in a code block. Examples that are
+// not a JSON object or array, such as calls of the method, are left out.
+fn example(value: &str) -> Option<Example> {
+    let (caption, value) = match value.strip_prefix("<caption>") {
+        Some(captioned) => captioned.split_once("</caption>")?,
+        None => ("", value),
+    };
+    let mut json = value.trim();
+    if let Some(code) = json.strip_prefix("```") {
+        // Skip the language of the code block
+        let (_, code) = code.split_once('\n')?;
+        json = code.trim_end().strip_suffix("```")?.trim();
+    }
+    (json.starts_with('{') || json.starts_with('[')).then(|| Example {
+        request: caption.to_lowercase().contains("request"),
+        json: json.to_string(),
+    })
+}
+
+// Declarations and members that JSDoc tags can be attached to
+#[derive(Clone, Copy, PartialEq)]
+pub enum Target {
+    Type,
+    Class,
+    Property,
+    Method,
+}
+
+impl Target {
+    fn name(&self) -> &'static str {
         match self {
-            ConversionType::Rust => ConversionType::map_type_rust,
-            ConversionType::Protobuf => ConversionType::map_type_proto,
-        }
-    }
-
-    fn map_ts_type_rust(&self, type_ann: TsType) -> (String, String) {
-        match type_ann {
-            TsType::TsTypeRef(TsTypeRef {
-                type_name: TsEntityName::Ident(ident),
-                ..
-            }) => (
-                String::from(""),
-                self.map_non_ts_keywords(ident.sym.to_string()),
-            ),
-
-            TsType::TsKeywordType(keyword) => (String::from(""), self.map_type()(keyword.kind)),
-            TsType::TsArrayType(TsArrayType {
-                elem_type: box TsType::TsKeywordType(TsKeywordType { kind, .. }),
-                ..
-            }) => (String::from(""), format!("Vec<{}>", self.map_type()(kind))),
-            TsType::TsArrayType(TsArrayType {
-                elem_type:
-                    box TsType::TsTypeRef(TsTypeRef {
-                        type_name: TsEntityName::Ident(ident),
-                        ..
-                    }),
-                ..
-            }) => (String::from(""), format!("Vec<{}>", ident.sym)),
-            _ => {
-                // println!("type_ann : {:#?}", type_ann);
-                // panic!("UNINPLEMENTED TSTYPE FOR PROPERTY INTERFACE")
-                (
-                    String::from(""),
-                    format!("UNINPLEMENTED TSTYPE FOR PROPERTY INTERFACE"),
-                )
-            }
+            Target::Type => "types",
+            Target::Class => "classes",
+            Target::Property => "properties",
+            Target::Method => "methods",
         }
     }
-    fn map_ts_type_proto(&self, type_ann: TsType) -> (String, String) {
-        match type_ann {
-            TsType::TsTypeRef(TsTypeRef {
-                type_name: TsEntityName::Ident(ident),
-                ..
-            }) => (
-                String::from(""),
-                self.map_non_ts_keywords(ident.sym.to_string()),
-            ),
-
-            TsType::TsKeywordType(keyword) => (String::from(""), self.map_type()(keyword.kind)),
-            TsType::TsArrayType(TsArrayType {
-                elem_type: box TsType::TsKeywordType(TsKeywordType { kind, .. }),
-                ..
-            }) => (String::from("repeated"), self.map_type()(kind)),
-            TsType::TsArrayType(TsArrayType {
-                elem_type:
-                    box TsType::TsTypeRef(TsTypeRef {
-                        type_name: TsEntityName::Ident(ident),
-                        ..
-                    }),
-                ..
-            }) => (String::from("repeated"), ident.sym.to_string()),
-            _ => {
-                // println!("type_ann : {:#?}", type_ann);
-                // panic!("UNINPLEMENTED TSTYPE FOR PROPERTY INTERFACE")
-                (
-                    String::from(""),
-                    format!("UNINPLEMENTED TSTYPE FOR PROPERTY INTERFACE"),
-                )
-            }
+}
+
+// State of the lowering of a module: the comments of the source, which carry the
+// JSDoc annotations, and what was collected along the way
+pub struct Context {
+    comments: SingleThreadedComments,
+    pub warnings: Warnings,
+    // Overrides set by JSDoc tags
+    pub overrides: Overrides,
+}
+
+impl Context {
+    pub fn new(comments: SingleThreadedComments) -> Context {
+        Context {
+            comments,
+            warnings: Warnings::default(),
+            overrides: Overrides::default(),
         }
     }
-    pub fn map_ts_types(&self, type_ann: TsType) -> (String, String) {
-        match self {
-            ConversionType::Rust => self.map_ts_type_rust(type_ann),
-            ConversionType::Protobuf => self.map_ts_type_proto(type_ann),
-        }
+
+    pub fn warn(&mut self, span: Span, message: String) {
+        self.warnings.warn(span, message);
     }
-    pub fn map_non_ts_keywords(&self, keyword: String) -> String {
-        match self {
-            ConversionType::Rust => match keyword.as_str() {
-                "BigNumber" => String::from("Decimal"),
-                _ => keyword,
-            },
-            ConversionType::Protobuf => match keyword.as_str() {
-                "BigNumber" => String::from("int64"),
-                _ => keyword,
-            },
-        }
-    }
-    pub fn extract_key_value_from_ts_type_element(
-        &self,
-        property: TsTypeElement,
-    ) -> (String, String, String, bool, bool) {
-        match property {
-            TsTypeElement::TsPropertySignature(TsPropertySignature {
-                key: box Expr::Ident(id),
-                type_ann: Some(TsTypeAnn { box type_ann, .. }),
-                optional,
-                ..
-            }) => {
-                let (prefix, value) = self.map_ts_types(type_ann);
-                (prefix, id.sym.to_string(), value, optional, false)
+
+    pub fn fallback(&mut self, span: Span, kind: &'static str, ty: TypeExpr) -> TypeExpr {
+        self.warnings.fallback(span, kind, ty)
+    }
+
+    // JSDoc comment right before `pos`, empty if there is none
+    pub fn jsdoc(&self, pos: BytePos) -> JsDoc {
+        let comments = self.comments.get_leading(pos).unwrap_or_default();
+        comments
+            .iter()
+            .rev()
+            .find(|comment| comment.kind == CommentKind::Block && comment.text.starts_with('*'))
+            .map(|comment| JsDoc::parse(&comment.text))
+            .unwrap_or_default()
+    }
+
+    // Record the overrides set by the JSDoc tags of the declaration or member at `span`,
+    // which is referred to as `name` in the overrides:
+    // - `@rust-type T` and `@proto-type T` map the type of a type, property or method
+    //   to `T`, as the `types` and `fields` mappings of the configuration do
+    // - `@rename name` and `@skip` rename and leave out the declaration or member
+    // - `@proto-field N` numbers a property in proto
+    // - `@number T` sets the representation of the numbers of a property
+    pub fn annotate(&mut self, span: Span, name: &str, target: Target) {
+        let mut mapping = Mapping::default();
+        for (tag, value) in self.jsdoc(span.lo).tags {
+            // The values of these tags are single words
+            let value = value.lines().next().unwrap_or_default().to_string();
+            let supported = match tag.as_str() {
+                "rust-type" | "proto-type" => target != Target::Class,
+                "rename" | "skip" => true,
+                "proto-field" | "number" => target == Target::Property,
+                // Other tags, such as `@param`, are documentation
+                _ => continue,
+            };
+            if !supported {
+                let message = format!("`@{}` is not supported on {}, ignored", tag, target.name());
+                self.warn(span, message);
+                continue;
             }
-            // return (key, val, optional)
-
-            // Handle conversion for hashmap type such as
-            // ```
-            // export interface MasternodeResult<T> {
-            //     [id: string]: T
-            // }
-            // ```
-            // to
-            // ```
-            // pub struct MasternodeResult<T>(HashMap<String, T>);
-            // ```
-            TsTypeElement::TsIndexSignature(TsIndexSignature {
-                params,
-                type_ann: Some(TsTypeAnn { box type_ann, .. }),
-                ..
-            }) => {
-                if let Some(TsFnParam::Ident(BindingIdent {
-                    type_ann:
-                        Some(TsTypeAnn {
-                            type_ann: box TsType::TsKeywordType(keyword),
-                            ..
-                        }),
-                    ..
-                })) = params.get(0)
-                {
-                    let (prefix, value) = self.map_ts_types(type_ann);
-                    return (prefix, self.map_type()(keyword.kind), value, false, true);
-                } else {
-                    unreachable!("found an unexpected ts type!");
+            if value.is_empty() && tag != "skip" {
+                self.warn(span, format!("`@{}` has no value, ignored", tag));
+                continue;
+            }
+
+            let name = name.to_string();
+            match tag.as_str() {
+                "rust-type" => mapping.rust = Some(value),
+                "proto-type" => mapping.proto = Some(value),
+                "rename" => {
+                    self.overrides.rename.insert(name, value);
                 }
+                "skip" => self.overrides.skip.push(name),
+                "proto-field" => match value.parse::<u32>() {
+                    Ok(number) if number > 0 => {
+                        self.overrides.proto_fields.insert(name, number);
+                    }
+                    _ => self.warn(
+                        span,
+                        format!("`{}` is not a valid proto field number, ignored", value),
+                    ),
+                },
+                "number" => match NumberType::try_from(value) {
+                    Ok(number) => {
+                        self.overrides.numbers.fields.insert(name, number);
+                    }
+                    Err(e) => self.warn(span, format!("{}, ignored", e)),
+                },
+                _ => unreachable!(),
             }
-            _ => unreachable!("found an unexpected ts type!"),
+        }
+
+        if mapping.rust.is_some() || mapping.proto.is_some() {
+            let mappings = match target {
+                Target::Type => &mut self.overrides.types,
+                _ => &mut self.overrides.fields,
+            };
+            mappings.insert(name.to_string(), mapping);
         }
     }
 }
 
-pub struct Param {
-    pub prefix: String,
-    pub key: String,
-    pub val: String,
-    pub optional: bool,
-    pub conversion_type: ConversionType,
+// Kind of a type that is not supported, as reported in fallbacks
+fn unsupported_kind(ty: &TsType) -> &'static str {
+    match ty {
+        TsType::TsKeywordType(_) => "keyword type",
+        TsType::TsThisType(_) => "this type",
+        TsType::TsFnOrConstructorType(_) => "function type",
+        TsType::TsTypeRef(_) => "qualified type name",
+        TsType::TsTypeQuery(_) => "typeof type",
+        TsType::TsOptionalType(_) | TsType::TsRestType(_) => "tuple element",
+        TsType::TsUnionOrIntersectionType(_) => "intersection type",
+        TsType::TsConditionalType(_) | TsType::TsInferType(_) => "conditional type",
+        TsType::TsTypeOperator(_) => "type operator",
+        TsType::TsIndexedAccessType(_) => "indexed access type",
+        TsType::TsMappedType(_) => "mapped type",
+        TsType::TsTypePredicate(_) => "type predicate",
+        TsType::TsImportType(_) => "import type",
+        _ => "type",
+    }
 }
 
-impl Param {
-    fn fmt_rust(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let val = self.val.clone();
-        let opt_val = if self.optional {
-            format!("Option<{}>", val)
-        } else {
-            val
-        };
-        let str = format!("{}: {}", self.key.to_case(Case::Snake), opt_val);
-        write!(f, "{},", str)
+// Primitive type of a keyword, if it has one. `any`, `unknown` and the nullish
+// keywords are deliberately represented as `Any`.
+fn lower_keyword(kind: TsKeywordTypeKind) -> Option<TypeExpr> {
+    Some(TypeExpr::Primitive(match kind {
+        TsKeywordTypeKind::TsStringKeyword => Primitive::String,
+        TsKeywordTypeKind::TsNumberKeyword => Primitive::Number(None),
+        TsKeywordTypeKind::TsBooleanKeyword => Primitive::Boolean,
+        TsKeywordTypeKind::TsObjectKeyword => Primitive::Object,
+        TsKeywordTypeKind::TsAnyKeyword
+        | TsKeywordTypeKind::TsUnknownKeyword
+        | TsKeywordTypeKind::TsNullKeyword
+        | TsKeywordTypeKind::TsUndefinedKeyword
+        | TsKeywordTypeKind::TsVoidKeyword => Primitive::Any,
+        _ => return None,
+    }))
+}
+
+fn is_nullish(ty: &TsType) -> bool {
+    matches!(
+        ty,
+        TsType::TsKeywordType(TsKeywordType {
+            kind: TsKeywordTypeKind::TsNullKeyword | TsKeywordTypeKind::TsUndefinedKeyword,
+            ..
+        })
+    )
+}
+
+// Value of a literal expression, such as `'active'`, `-1` or `true`
+pub fn lower_literal(expr: &Expr) -> Option<Literal> {
+    match expr {
+        Expr::Lit(Lit::Str(lit)) => Some(Literal::String(lit.value.to_string())),
+        Expr::Lit(Lit::Num(lit)) => Some(Literal::Number(lit.value)),
+        Expr::Lit(Lit::Bool(lit)) => Some(Literal::Boolean(lit.value)),
+        Expr::Unary(UnaryExpr {
+            op: UnaryOp::Minus,
+            arg,
+            ..
+        }) => match &**arg {
+            Expr::Lit(Lit::Num(lit)) => Some(Literal::Number(-lit.value)),
+            _ => None,
+        },
+        _ => None,
     }
-    fn fmt_proto(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {} {}",
-            self.prefix,
-            self.val.clone(),
-            self.key.to_case(Case::Snake),
-        )
+}
+
+pub fn lower_type(type_ann: TsType, cx: &mut Context) -> TypeExpr {
+    let span = type_ann.span();
+    let any = TypeExpr::Primitive(Primitive::Any);
+    match type_ann {
+        TsType::TsKeywordType(keyword) => match lower_keyword(keyword.kind) {
+            Some(ty) => ty,
+            None => cx.fallback(span, "keyword type", any),
+        },
+        TsType::TsTypeRef(TsTypeRef {
+            type_name: TsEntityName::Ident(ident),
+            type_params,
+            ..
+        }) => {
+            let mut args: Vec<TypeExpr> = match type_params {
+                Some(TsTypeParamInstantiation { params, .. }) => params
+                    .into_iter()
+                    .map(|param| lower_type(*param, cx))
+                    .collect(),
+                None => Vec::new(),
+            };
+            match (ident.sym.as_ref(), args.len()) {
+                ("Array", 1) => TypeExpr::Array(Box::new(args.remove(0))),
+                ("Record", 2) => {
+                    let value = args.remove(1);
+                    TypeExpr::Map(Box::new(args.remove(0)), Box::new(value))
+                }
+                (name, _) => TypeExpr::Ref {
+                    name: name.to_string(),
+                    args,
+                },
+            }
+        }
+        TsType::TsArrayType(TsArrayType { elem_type, .. }) => {
+            TypeExpr::Array(Box::new(lower_type(*elem_type, cx)))
+        }
+        TsType::TsUnionOrIntersectionType(TsUnionOrIntersectionType::TsUnionType(
+            TsUnionType { types, .. },
+        )) => {
+            let nullable = types.iter().any(|ty| is_nullish(ty));
+            let members = types
+                .into_iter()
+                .filter(|ty| !is_nullish(ty))
+                .map(|ty| lower_type(*ty, cx))
+                .collect();
+            let ty = TypeExpr::union(members, nullable);
+            match ty.clone().split_optional() {
+                (TypeExpr::Union(_), _) => cx.fallback(span, "union", ty),
+                _ => ty,
+            }
+        }
+        TsType::TsTupleType(TsTupleType { elem_types, .. }) => TypeExpr::Tuple(
+            elem_types
+                .into_iter()
+                .map(|TsTupleElement { ty, .. }| lower_type(ty, cx))
+                .collect(),
+        ),
+        // Literal types are represented by the type of the literal
+        TsType::TsLitType(TsLitType { lit, .. }) => TypeExpr::Primitive(match lit {
+            TsLit::Str(_) | TsLit::Tpl(_) => Primitive::String,
+            TsLit::Number(_) | TsLit::BigInt(_) => Primitive::Number(None),
+            TsLit::Bool(_) => Primitive::Boolean,
+        }),
+        TsType::TsParenthesizedType(TsParenthesizedType { type_ann, .. }) => {
+            lower_type(*type_ann, cx)
+        }
+        // Inline objects made of an index signature only are maps
+        TsType::TsTypeLit(TsTypeLit { mut members, .. }) if members.len() == 1 => {
+            match lower_type_element(members.remove(0), cx) {
+                Some(Element::Map(key, value)) => TypeExpr::Map(Box::new(key), Box::new(value)),
+                _ => cx.fallback(
+                    span,
+                    "inline object",
+                    TypeExpr::Primitive(Primitive::Object),
+                ),
+            }
+        }
+        TsType::TsTypeLit(_) => cx.fallback(
+            span,
+            "inline object",
+            TypeExpr::Primitive(Primitive::Object),
+        ),
+        ty => cx.fallback(span, unsupported_kind(&ty), any),
     }
 }
 
-impl Display for Param {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.conversion_type {
-            ConversionType::Protobuf => self.fmt_proto(f),
-            ConversionType::Rust => self.fmt_rust(f),
+pub enum Element {
+    Field(Field),
+    // Key and value of an index signature
+    Map(TypeExpr, TypeExpr),
+}
+
+pub fn lower_type_element(property: TsTypeElement, cx: &mut Context) -> Option<Element> {
+    let span = property.span();
+    match property {
+        TsTypeElement::TsPropertySignature(TsPropertySignature {
+            key,
+            computed,
+            type_ann,
+            optional,
+            ..
+        }) => {
+            let name = match *key {
+                Expr::Ident(id) if !computed => id.sym.to_string(),
+                Expr::Lit(Lit::Str(key)) => key.value.to_string(),
+                _ => {
+                    cx.warn(span, String::from("property key is not supported, skipped"));
+                    return None;
+                }
+            };
+            let ty = match type_ann {
+                Some(type_ann) => lower_type(*type_ann.type_ann, cx),
+                None => {
+                    cx.warn(
+                        span,
+                        format!("property `{}` has no type annotation, skipped", name),
+                    );
+                    return None;
+                }
+            };
+            Some(Element::Field(Field::new(
+                name,
+                if optional { ty.optional() } else { ty },
+            )))
         }
+
+        // Handle conversion for hashmap type such as
+        // ```
+        // export interface MasternodeResult<T> {
+        //     [id: string]: T
+        // }
+        // ```
+        // to
+        // ```
+        // pub struct MasternodeResult<T>(HashMap<String, T>);
+        // ```
+        TsTypeElement::TsIndexSignature(TsIndexSignature {
+            params,
+            type_ann: Some(type_ann),
+            ..
+        }) => {
+            let keyword = match params.first() {
+                Some(TsFnParam::Ident(BindingIdent {
+                    type_ann: Some(key_ann),
+                    ..
+                })) => match &*key_ann.type_ann {
+                    TsType::TsKeywordType(keyword) => Some(keyword),
+                    _ => None,
+                },
+                _ => None,
+            };
+            let key = if let Some(keyword) = keyword {
+                match lower_keyword(keyword.kind) {
+                    Some(key) => key,
+                    None => cx.fallback(
+                        keyword.span,
+                        "keyword type",
+                        TypeExpr::Primitive(Primitive::Any),
+                    ),
+                }
+            } else {
+                cx.warn(
+                    span,
+                    String::from("index signature key is not supported, using `string`"),
+                );
+                TypeExpr::Primitive(Primitive::String)
+            };
+            Some(Element::Map(key, lower_type(*type_ann.type_ann, cx)))
+        }
+        TsTypeElement::TsMethodSignature(_) => {
+            cx.warn(
+                span,
+                String::from("method signature is not supported, skipped"),
+            );
+            None
+        }
+        _ => {
+            cx.warn(
+                span,
+                String::from("interface member is not supported, skipped"),
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lower_text;
+
+    // Text of a `/** */` comment as swc hands it, without its delimiters
+    const VAULT: &str = "*
+     * Vault of a loan, holding its collateral.
+     *
+     * Closed vaults can't be reopened.
+     * @param {string} vaultId - id of the vault,
+     *   as returned by `createVault`
+     * @param [verbose=true] include the loans
+     * @param limit
+     * @deprecated use `getVaultInfo`,
+     * which also returns the loans
+     * @proto-field 3
+     ";
+
+    #[test]
+    fn text_and_tags() {
+        let jsdoc = JsDoc::parse(VAULT);
+        assert_eq!(
+            jsdoc.text,
+            "Vault of a loan, holding its collateral.\n\nClosed vaults can't be reopened."
+        );
+        let tags: Vec<&str> = jsdoc.tags.iter().map(|(tag, _)| tag.as_str()).collect();
+        assert_eq!(
+            tags,
+            ["param", "param", "param", "deprecated", "proto-field"]
+        );
+        assert_eq!(jsdoc.values("proto-field").collect::<Vec<_>>(), ["3"]);
+    }
+
+    #[test]
+    fn multi_line_tags() {
+        let jsdoc = JsDoc::parse(VAULT);
+        assert_eq!(
+            jsdoc.values("deprecated").next(),
+            Some("use `getVaultInfo`,\nwhich also returns the loans")
+        );
+        assert_eq!(
+            jsdoc.doc().deprecated.as_deref(),
+            Some("use `getVaultInfo`,\nwhich also returns the loans")
+        );
+    }
+
+    #[test]
+    fn params() {
+        let jsdoc = JsDoc::parse(VAULT);
+        assert_eq!(
+            jsdoc.param("vaultId").as_deref(),
+            Some("id of the vault,\nas returned by `createVault`")
+        );
+        assert_eq!(jsdoc.param("verbose").as_deref(), Some("include the loans"));
+        assert_eq!(jsdoc.param("limit").as_deref(), Some(""));
+        assert_eq!(jsdoc.param("vault"), None);
+    }
+
+    #[test]
+    fn proto_field_numbers() {
+        let text = "export interface Vault {\n  /** @proto-field 3 */\n  id: string\n  \
+                    /** @proto-field 0 */\n  owner: string\n  \
+                    /** @proto-field two */\n  state: string\n}\n";
+        let (_, overrides) = lower_text(text);
+        let numbers: Vec<(&str, u32)> = overrides
+            .proto_fields
+            .iter()
+            .map(|(field, number)| (field.as_str(), *number))
+            .collect();
+        assert_eq!(numbers, [("Vault.id", 3)]);
     }
 }
@@ -0,0 +1,325 @@
+// Forward the rpcs of a generated bridge to a mock JSON-RPC client, checking that
+// the requests are mapped to the client calls and that the results are converted
+// back to the prost responses.
+//
+// The files in `tests/bridge` are generated from `vault.ts` with
+// `generate -f rust,proto,conversions,bridge -d tests/bridge tests/bridge/vault.ts`,
+// and `prost/vault.rs` from `vault.proto` by tonic-build 0.11.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use parser_jellyfish_rust::{convert, DecimalRepr, Format, Options, Source};
+
+// The client method of the unimplemented rpc is unused
+#[allow(dead_code)]
+mod vault {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::Value;
+
+    pub mod proto {
+        include!("bridge/prost/vault.rs");
+    }
+
+    include!("bridge/vault.rs");
+    include!("bridge/vault_client.rs");
+
+    pub mod conversions {
+        include!("bridge/vault_conversions.rs");
+    }
+
+    // The bridge converts every value with `try_from`, whether its conversion can fail
+    // or not
+    #[allow(clippy::redundant_closure, clippy::unnecessary_fallible_conversions)]
+    pub mod bridge {
+        include!("bridge/vault_bridge.rs");
+    }
+
+    // Argument of a JSON-RPC call
+    pub struct Arg(Value);
+
+    impl<T: Serialize> From<T> for Arg {
+        fn from(value: T) -> Self {
+            Arg(serde_json::to_value(value).unwrap())
+        }
+    }
+
+    type Result<T> = std::result::Result<T, String>;
+
+    // Commands received by the client, along with their arguments
+    pub type Calls = Arc<Mutex<Vec<(String, Vec<Value>)>>>;
+
+    // JSON-RPC client answering each command with a fixed result, and recording the
+    // calls it receives
+    pub struct Client {
+        results: HashMap<&'static str, Value>,
+        calls: Calls,
+    }
+
+    impl Client {
+        pub fn new(results: HashMap<&'static str, Value>, calls: Calls) -> Client {
+            Client { results, calls }
+        }
+
+        async fn call<T: serde::de::DeserializeOwned>(&self, cmd: &str, args: &[Arg]) -> Result<T> {
+            let args = args.iter().map(|Arg(arg)| arg.clone()).collect();
+            self.calls.lock().unwrap().push((cmd.to_string(), args));
+            let result = self
+                .results
+                .get(cmd)
+                .ok_or_else(|| format!("method not found: {}", cmd))?;
+            serde_json::from_value(result.clone()).map_err(|e| e.to_string())
+        }
+    }
+}
+
+use serde_json::json;
+use tonic::{Code, Request};
+use vault::bridge::VaultsService;
+use vault::proto;
+use vault::proto::vaults_server::Vaults;
+
+fn service(results: HashMap<&'static str, serde_json::Value>) -> VaultsService {
+    service_with_calls(results, vault::Calls::default())
+}
+
+fn service_with_calls(
+    results: HashMap<&'static str, serde_json::Value>,
+    calls: vault::Calls,
+) -> VaultsService {
+    VaultsService::new(vault::Client::new(results, calls))
+}
+
+fn vault() -> serde_json::Value {
+    json!({
+        "vaultId": "v1",
+        "ownerAddress": "owner",
+        "state": "inLiquidation",
+        "collateralAmounts": ["10@DFI"],
+        "loanValue": "12.5",
+        "type": "standard",
+    })
+}
+
+#[tokio::test]
+async fn forwards_message_parameters() {
+    let results = HashMap::from([("createvault", json!("v1"))]);
+    let service = service(results);
+    let request = proto::CreateVaultRequest {
+        vault: Some(proto::CreateVault {
+            owner_address: String::from("owner"),
+            loan_scheme_id: Some(String::from("scheme")),
+        }),
+    };
+    let response = service.create_vault(Request::new(request)).await.unwrap();
+    assert_eq!(response.into_inner().result, "v1");
+}
+
+#[tokio::test]
+async fn converts_message_results() {
+    let results = HashMap::from([("getvault", vault())]);
+    let service = service(results);
+    let request = proto::GetVaultRequest {
+        vault_id: String::from("v1"),
+    };
+    let response = service.get_vault(Request::new(request)).await.unwrap();
+    assert_eq!(
+        response.into_inner(),
+        proto::Vault {
+            vault_id: String::from("v1"),
+            owner_address: String::from("owner"),
+            state: proto::VaultState::InLiquidation as i32,
+            collateral_amounts: vec![String::from("10@DFI")],
+            loan_value: String::from("12.5"),
+            r#type: String::from("standard"),
+        }
+    );
+}
+
+#[tokio::test]
+async fn wraps_enum_and_array_results() {
+    let results = HashMap::from([
+        ("getvaultstate", json!("frozen")),
+        ("listvaults", json!([vault(), vault()])),
+    ]);
+    let service = service(results);
+
+    let request = proto::GetVaultStateRequest {
+        vault_id: String::from("v1"),
+    };
+    let response = service
+        .get_vault_state(Request::new(request))
+        .await
+        .unwrap();
+    assert_eq!(
+        response.into_inner().result,
+        proto::VaultState::Frozen as i32
+    );
+
+    let request = proto::ListVaultsRequest {
+        limit: Some(2),
+        verbose: None,
+    };
+    let response = service.list_vaults(Request::new(request)).await.unwrap();
+    assert_eq!(response.into_inner().result.len(), 2);
+}
+
+#[tokio::test]
+async fn reads_enums_by_their_values() {
+    // The results hold the values declared in TypeScript, not the variant names
+    let results = HashMap::from([("getvaultstate", json!("Frozen"))]);
+    let service = service(results);
+    let request = proto::GetVaultStateRequest {
+        vault_id: String::from("v1"),
+    };
+    let status = service
+        .get_vault_state(Request::new(request))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+}
+
+#[tokio::test]
+async fn converts_map_results() {
+    let results = HashMap::from([("getcollateral", json!({"DFI": "10.5", "BTC": "0.1"}))]);
+    let service = service(results);
+    let request = proto::GetCollateralRequest {
+        vault_id: String::from("v1"),
+    };
+    let response = service.get_collateral(Request::new(request)).await.unwrap();
+    let expected = HashMap::from([
+        (String::from("DFI"), String::from("10.5")),
+        (String::from("BTC"), String::from("0.1")),
+    ]);
+    assert_eq!(response.into_inner().result, expected);
+}
+
+#[tokio::test]
+async fn maps_requests_to_client_calls() {
+    let results = HashMap::from([("createvault", json!("v1")), ("listvaults", json!([]))]);
+    let calls = vault::Calls::default();
+    let service = service_with_calls(results, calls.clone());
+    let request = proto::CreateVaultRequest {
+        vault: Some(proto::CreateVault {
+            owner_address: String::from("owner"),
+            loan_scheme_id: None,
+        }),
+    };
+    service.create_vault(Request::new(request)).await.unwrap();
+    let request = proto::ListVaultsRequest {
+        limit: Some(5),
+        verbose: Some(false),
+    };
+    service.list_vaults(Request::new(request)).await.unwrap();
+
+    // The omitted optional parameter is sent as `null`
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![
+            (
+                String::from("createvault"),
+                vec![json!("owner"), json!(null)]
+            ),
+            (String::from("listvaults"), vec![json!(5), json!(false)]),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn leaves_out_optional_parameters() {
+    let results = HashMap::from([("listvaults", json!([]))]);
+    let calls = vault::Calls::default();
+    let service = service_with_calls(results, calls.clone());
+    let request = proto::ListVaultsRequest {
+        limit: None,
+        verbose: None,
+    };
+    service.list_vaults(Request::new(request)).await.unwrap();
+
+    // `limit` has no default and is sent as `null`, `verbose` takes its default
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![(String::from("listvaults"), vec![json!(null), json!(true)])]
+    );
+}
+
+#[tokio::test]
+async fn rejects_invalid_requests() {
+    let service = service(HashMap::new());
+
+    let request = proto::CreateVaultRequest { vault: None };
+    let status = service
+        .create_vault(Request::new(request))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    // `limit` is a `u64` in the client
+    let request = proto::ListVaultsRequest {
+        limit: Some(-1),
+        verbose: None,
+    };
+    let status = service
+        .list_vaults(Request::new(request))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn reports_client_errors() {
+    let service = service(HashMap::new());
+    let request = proto::GetVaultRequest {
+        vault_id: String::from("v1"),
+    };
+    let status = service.get_vault(Request::new(request)).await.unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+    assert!(status.message().contains("getvault"));
+}
+
+#[tokio::test]
+async fn leaves_unconvertible_rpcs_unimplemented() {
+    let calls = vault::Calls::default();
+    let service = service_with_calls(HashMap::new(), calls.clone());
+    let request = proto::GetHistoryRequest {
+        vault_id: String::from("v1"),
+        since: None,
+    };
+    let status = service
+        .get_history(Request::new(request))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unimplemented);
+    assert!(calls.lock().unwrap().is_empty());
+}
+
+// The fixtures are generated from `vault.ts` by the current converter
+#[test]
+fn fixtures_are_up_to_date() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/bridge");
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    let sources = [Source {
+        path: String::from("tests/bridge/vault.ts"),
+        text: read("vault.ts"),
+    }];
+
+    for format in [
+        Format::Rust,
+        Format::Proto,
+        Format::Conversions,
+        Format::Bridge,
+    ] {
+        let options = Options {
+            format,
+            decimal: DecimalRepr::String,
+            ..Options::default()
+        };
+        let output = convert(&sources, &options).unwrap();
+        let module = &output.modules[0];
+        assert_eq!(module.text, read(&module.file_name), "{}", module.file_name);
+        if let Some(client) = &module.client {
+            assert_eq!(*client, read("vault_client.rs"));
+        }
+    }
+}
@@ -0,0 +1,35 @@
+// Generated by parser_jellyfish_rust from tests/bridge/vault.ts, do not edit.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum VaultState {
+  #[serde(rename = "active")]
+  Active,
+  #[serde(rename = "frozen")]
+  Frozen,
+  #[serde(rename = "inLiquidation")]
+  InLiquidation,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateVault {
+  owner_address: String,
+  loan_scheme_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Vault {
+  vault_id: String,
+  owner_address: String,
+  state: VaultState,
+  collateral_amounts: Vec<String>,
+  loan_value: Decimal,
+  /// Keyword field, named `r#type` in the serde and prost types
+  r#type: String,
+}
+
@@ -0,0 +1,46 @@
+export enum VaultState {
+  ACTIVE = 'active',
+  FROZEN = 'frozen',
+  IN_LIQUIDATION = 'inLiquidation',
+}
+
+export interface CreateVault {
+  ownerAddress: string
+  loanSchemeId?: string
+}
+
+export interface Vault {
+  vaultId: string
+  ownerAddress: string
+  state: VaultState
+  collateralAmounts: string[]
+  loanValue: BigNumber
+  /** Keyword field, named `r#type` in the serde and prost types */
+  type: string
+}
+
+export class Vaults {
+  async createVault (vault: CreateVault): Promise<string> {
+    return await this.client.call('createvault', [vault.ownerAddress, vault.loanSchemeId], 'number')
+  }
+
+  async getVault (vaultId: string): Promise<Vault> {
+    return await this.client.call('getvault', [vaultId], 'bignumber')
+  }
+
+  async getVaultState (vaultId: string): Promise<VaultState> {
+    return await this.client.call('getvaultstate', [vaultId], 'number')
+  }
+
+  async listVaults (limit?: number, verbose = true): Promise<Vault[]> {
+    return await this.client.call('listvaults', [limit, verbose], 'bignumber')
+  }
+
+  async getCollateral (vaultId: string): Promise<Record<string, BigNumber>> {
+    return await this.client.call('getcollateral', [vaultId], 'bignumber')
+  }
+
+  async getHistory (vaultId: string, since: Date): Promise<string[]> {
+    return await this.client.call('gethistory', [vaultId, since], 'number')
+  }
+}
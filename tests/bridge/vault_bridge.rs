@@ -0,0 +1,184 @@
+use super::conversions::*;
+use super::*;
+
+impl From<ConversionError> for tonic::Status {
+    fn from(e: ConversionError) -> Self {
+        tonic::Status::invalid_argument(e.to_string())
+    }
+}
+
+// gRPC service forwarding every rpc of `Vaults` to the JSON-RPC client.
+pub struct VaultsService {
+    client: Client,
+}
+
+impl VaultsService {
+    pub fn new(client: Client) -> Self {
+        VaultsService { client }
+    }
+}
+
+#[tonic::async_trait]
+impl proto::vaults_server::Vaults for VaultsService {
+    async fn create_vault(
+        &self,
+        request: tonic::Request<proto::CreateVaultRequest>,
+    ) -> std::result::Result<tonic::Response<proto::CreateVaultResponse>, tonic::Status> {
+        let (vault,) = create_vault_params(request.into_inner())?;
+        let result = self
+            .client
+            .create_vault(vault)
+            .await
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        let response = proto::CreateVaultResponse::try_from(result)
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_vault(
+        &self,
+        request: tonic::Request<proto::GetVaultRequest>,
+    ) -> std::result::Result<tonic::Response<proto::Vault>, tonic::Status> {
+        let (vault_id,) = get_vault_params(request.into_inner())?;
+        let result = self
+            .client
+            .get_vault(vault_id)
+            .await
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        let response = proto::Vault::try_from(result)
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_vault_state(
+        &self,
+        request: tonic::Request<proto::GetVaultStateRequest>,
+    ) -> std::result::Result<tonic::Response<proto::GetVaultStateResponse>, tonic::Status> {
+        let (vault_id,) = get_vault_state_params(request.into_inner())?;
+        let result = self
+            .client
+            .get_vault_state(vault_id)
+            .await
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        let response = proto::GetVaultStateResponse::try_from(result)
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn list_vaults(
+        &self,
+        request: tonic::Request<proto::ListVaultsRequest>,
+    ) -> std::result::Result<tonic::Response<proto::ListVaultsResponse>, tonic::Status> {
+        let (limit, verbose) = list_vaults_params(request.into_inner())?;
+        let result = self
+            .client
+            .list_vaults(limit, verbose)
+            .await
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        let response = proto::ListVaultsResponse::try_from(result)
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_collateral(
+        &self,
+        request: tonic::Request<proto::GetCollateralRequest>,
+    ) -> std::result::Result<tonic::Response<proto::GetCollateralResponse>, tonic::Status> {
+        let (vault_id,) = get_collateral_params(request.into_inner())?;
+        let result = self
+            .client
+            .get_collateral(vault_id)
+            .await
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        let response = proto::GetCollateralResponse::try_from(result)
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_history(
+        &self,
+        _request: tonic::Request<proto::GetHistoryRequest>,
+    ) -> std::result::Result<tonic::Response<proto::GetHistoryResponse>, tonic::Status> {
+        // The parameters or the result of `getHistory` can't be converted (date)
+        Err(tonic::Status::unimplemented("`GetHistory` can't be forwarded"))
+    }
+}
+
+// Parameters of `Client::create_vault`, converted from the rpc request
+fn create_vault_params(
+    request: proto::CreateVaultRequest,
+) -> std::result::Result<(CreateVault,), ConversionError> {
+    Ok((
+        CreateVault::try_from(request.vault.ok_or(ConversionError::MissingField("vault"))?)?,
+    ))
+}
+
+impl TryFrom<String> for proto::CreateVaultResponse {
+    type Error = ConversionError;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        Ok(Self { result: value })
+    }
+}
+
+// Parameters of `Client::get_vault`, converted from the rpc request
+fn get_vault_params(
+    request: proto::GetVaultRequest,
+) -> std::result::Result<(String,), ConversionError> {
+    Ok((
+        request.vault_id,
+    ))
+}
+
+// Parameters of `Client::get_vault_state`, converted from the rpc request
+fn get_vault_state_params(
+    request: proto::GetVaultStateRequest,
+) -> std::result::Result<(String,), ConversionError> {
+    Ok((
+        request.vault_id,
+    ))
+}
+
+impl TryFrom<VaultState> for proto::GetVaultStateResponse {
+    type Error = ConversionError;
+
+    fn try_from(value: VaultState) -> std::result::Result<Self, Self::Error> {
+        Ok(Self { result: proto::VaultState::from(value) as i32 })
+    }
+}
+
+// Parameters of `Client::list_vaults`, converted from the rpc request
+fn list_vaults_params(
+    request: proto::ListVaultsRequest,
+) -> std::result::Result<(Option<u64>, Option<bool>), ConversionError> {
+    Ok((
+        request.limit.map(|v| u64::try_from(v)).transpose()?,
+        request.verbose,
+    ))
+}
+
+impl TryFrom<Vec<Vault>> for proto::ListVaultsResponse {
+    type Error = ConversionError;
+
+    fn try_from(value: Vec<Vault>) -> std::result::Result<Self, Self::Error> {
+        Ok(Self { result: value.into_iter().map(|v| proto::Vault::try_from(v)).collect::<std::result::Result<Vec<_>, _>>()? })
+    }
+}
+
+// Parameters of `Client::get_collateral`, converted from the rpc request
+fn get_collateral_params(
+    request: proto::GetCollateralRequest,
+) -> std::result::Result<(String,), ConversionError> {
+    Ok((
+        request.vault_id,
+    ))
+}
+
+impl TryFrom<HashMap<String, Decimal>> for proto::GetCollateralResponse {
+    type Error = ConversionError;
+
+    fn try_from(value: HashMap<String, Decimal>) -> std::result::Result<Self, Self::Error> {
+        Ok(Self { result: value.into_iter().map(|(k, v)| (k, v.to_string())).collect() })
+    }
+}
+
@@ -0,0 +1,28 @@
+// Generated by parser_jellyfish_rust from tests/bridge/vault.ts, do not edit.
+
+impl Client {
+    pub async fn create_vault(&self, vault: CreateVault) -> Result<String> {
+        self.call("createvault", &[vault.owner_address.into(), vault.loan_scheme_id.into()]).await
+    }
+
+    pub async fn get_vault(&self, vault_id: String) -> Result<Vault> {
+        self.call("getvault", &[vault_id.into()]).await
+    }
+
+    pub async fn get_vault_state(&self, vault_id: String) -> Result<VaultState> {
+        self.call("getvaultstate", &[vault_id.into()]).await
+    }
+
+    pub async fn list_vaults(&self, limit: Option<u64>, verbose: Option<bool>) -> Result<Vec<Vault>> {
+        let verbose = verbose.unwrap_or(true);
+        self.call("listvaults", &[limit.into(), verbose.into()]).await
+    }
+
+    pub async fn get_collateral(&self, vault_id: String) -> Result<HashMap<String, Decimal>> {
+        self.call("getcollateral", &[vault_id.into()]).await
+    }
+
+    pub async fn get_history(&self, vault_id: String, since: String) -> Result<Vec<String>> {
+        self.call("gethistory", &[vault_id.into(), since.into()]).await
+    }
+}
@@ -0,0 +1,109 @@
+use rust_decimal::Decimal;
+
+use super::*;
+
+#[derive(Debug)]
+pub enum ConversionError {
+    MissingField(&'static str),
+    OutOfRange,
+    InvalidDecimal(rust_decimal::Error),
+    UnknownEnumValue(i32),
+}
+
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConversionError::MissingField(field) => write!(f, "missing field `{}`", field),
+            ConversionError::OutOfRange => write!(f, "value out of range"),
+            ConversionError::InvalidDecimal(e) => write!(f, "invalid decimal: {}", e),
+            ConversionError::UnknownEnumValue(value) => write!(f, "unknown enum value {}", value),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl From<std::num::TryFromIntError> for ConversionError {
+    fn from(_: std::num::TryFromIntError) -> Self {
+        ConversionError::OutOfRange
+    }
+}
+
+impl From<rust_decimal::Error> for ConversionError {
+    fn from(e: rust_decimal::Error) -> Self {
+        ConversionError::InvalidDecimal(e)
+    }
+}
+
+impl From<std::convert::Infallible> for ConversionError {
+    fn from(e: std::convert::Infallible) -> Self {
+        match e {}
+    }
+}
+
+impl From<VaultState> for proto::VaultState {
+    fn from(value: VaultState) -> Self {
+        match value {
+            VaultState::Active => proto::VaultState::Active,
+            VaultState::Frozen => proto::VaultState::Frozen,
+            VaultState::InLiquidation => proto::VaultState::InLiquidation,
+        }
+    }
+}
+
+impl From<proto::VaultState> for VaultState {
+    fn from(value: proto::VaultState) -> Self {
+        match value {
+            proto::VaultState::Active => VaultState::Active,
+            proto::VaultState::Frozen => VaultState::Frozen,
+            proto::VaultState::InLiquidation => VaultState::InLiquidation,
+        }
+    }
+}
+
+impl From<CreateVault> for proto::CreateVault {
+    fn from(value: CreateVault) -> Self {
+        Self {
+            owner_address: value.owner_address,
+            loan_scheme_id: value.loan_scheme_id,
+        }
+    }
+}
+
+impl From<proto::CreateVault> for CreateVault {
+    fn from(value: proto::CreateVault) -> Self {
+        Self {
+            owner_address: value.owner_address,
+            loan_scheme_id: value.loan_scheme_id,
+        }
+    }
+}
+
+impl From<Vault> for proto::Vault {
+    fn from(value: Vault) -> Self {
+        Self {
+            vault_id: value.vault_id,
+            owner_address: value.owner_address,
+            state: proto::VaultState::from(value.state) as i32,
+            collateral_amounts: value.collateral_amounts,
+            loan_value: value.loan_value.to_string(),
+            r#type: value.r#type,
+        }
+    }
+}
+
+impl TryFrom<proto::Vault> for Vault {
+    type Error = ConversionError;
+
+    fn try_from(value: proto::Vault) -> std::result::Result<Self, Self::Error> {
+        Ok(Self {
+            vault_id: value.vault_id,
+            owner_address: value.owner_address,
+            state: proto::VaultState::try_from(value.state).map(VaultState::from).map_err(|_| ConversionError::UnknownEnumValue(value.state))?,
+            collateral_amounts: value.collateral_amounts,
+            loan_value: value.loan_value.parse::<Decimal>()?,
+            r#type: value.r#type,
+        })
+    }
+}
+
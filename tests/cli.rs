@@ -0,0 +1,251 @@
+// Runs of the command-line interface, checking its exit status and what it reports
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// Scratch directory of the tests, where each test uses paths of its own
+fn scratch(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("cli")
+        .join(path)
+}
+
+// TypeScript source written to a file of its own, as the CLI reads its inputs from
+// files
+fn input(path: &str, text: &str) -> PathBuf {
+    let path = scratch(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, text).unwrap();
+    path
+}
+
+fn run(args: &[&str], inputs: &[&PathBuf]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_parser_jellyfish_rust"))
+        .args(args)
+        .args(inputs)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+const VAULT: &str = "export interface Vault {\n  id: string\n}\n";
+
+// The union has no direct counterpart, it's emitted as a `serde_json::Value`
+const FALLBACK: &str = "export interface Vault {\n  id: string\n  amount: string | number\n}\n";
+
+#[test]
+fn fallbacks_are_listed() {
+    let path = input("fallbacks_are_listed.ts", FALLBACK);
+    let output = run(&["rust"], &[&path]);
+    assert!(output.status.success());
+    assert!(!output.stdout.is_empty());
+    let stderr = stderr(&output);
+    assert!(stderr.starts_with("construct  location"));
+    assert!(stderr.contains("fallbacks_are_listed.ts:3:11  serde_json::Value\n"));
+    assert!(stderr.ends_with("1 fallback(s)\n"));
+}
+
+#[test]
+fn strict_fails_on_fallbacks() {
+    let path = input("strict_fails_on_fallbacks.ts", FALLBACK);
+    let output = run(&["rust", "--strict"], &[&path]);
+    assert_eq!(output.status.code(), Some(1));
+    // Nothing is written when the run fails
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn strict_fails_on_skipped_constructs() {
+    let text = "export interface Vault {\n  id: string\n  close(): void\n}\n";
+    let path = input("strict_fails_on_skipped_constructs.ts", text);
+    let output = run(&["proto", "--strict"], &[&path]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("method signature is not supported, skipped"));
+}
+
+#[test]
+fn strict_succeeds_without_fallbacks() {
+    let path = input("strict_succeeds_without_fallbacks.ts", VAULT);
+    let output = run(&["rust", "--strict"], &[&path]);
+    assert!(output.status.success());
+    assert!(stderr(&output).is_empty());
+}
+
+// JSON objects written to stderr with `--message-format json`, one per line
+fn json_messages(output: &Output) -> Vec<serde_json::Value> {
+    stderr(output)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn json_diagnostics() {
+    let text = "export interface Vault {\n  id: string\n  amount: string | number\n  \
+                close(): void\n}\n";
+    let path = input("json_diagnostics.ts", text);
+    let output = run(&["rust", "--message-format", "json"], &[&path]);
+    assert!(output.status.success());
+    let file = path.to_str().unwrap();
+    assert_eq!(
+        json_messages(&output),
+        [
+            serde_json::json!({
+                "type": "diagnostic",
+                "severity": "warning",
+                "code": "unsupported",
+                "message": "method signature is not supported, skipped",
+                "file": file,
+                "line": 4,
+                "column": 3,
+                "suggested_output": null,
+            }),
+            serde_json::json!({
+                "type": "diagnostic",
+                "severity": "warning",
+                "code": "fallback",
+                "message": "union has no direct counterpart",
+                "file": file,
+                "line": 3,
+                "column": 11,
+                "suggested_output": "serde_json::Value",
+            }),
+            serde_json::json!({
+                "type": "summary",
+                "interfaces": 1,
+                "enums": 0,
+                "aliases": 0,
+                "methods": 0,
+                "skipped": 1,
+                "fallbacks": 1,
+                "errors": 0,
+                "warnings": 2,
+            }),
+        ]
+    );
+}
+
+#[test]
+fn json_errors() {
+    let path = input("json_errors.ts", "export interface Vault {\n  id: string\n");
+    let output = run(&["proto", "--message-format", "json"], &[&path]);
+    assert_eq!(output.status.code(), Some(1));
+    let messages = json_messages(&output);
+    let (summary, diagnostics) = messages.split_last().unwrap();
+    assert!(!diagnostics.is_empty());
+    for diagnostic in diagnostics {
+        assert_eq!(diagnostic["type"], "diagnostic");
+        assert_eq!(diagnostic["severity"], "error");
+        assert_eq!(diagnostic["code"], "parse");
+    }
+    assert_eq!(summary["type"], "summary");
+    assert_eq!(summary["errors"], diagnostics.len());
+}
+
+// Runs rejected before anything is converted, along with their error
+fn rejected(output: &Output, error: &str) {
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(stderr(output), format!("error: {}\n", error));
+}
+
+#[test]
+fn out_holds_a_single_module() {
+    let vault = input("out_holds_a_single_module/vault.ts", VAULT);
+    let loan = input("out_holds_a_single_module/loan.ts", VAULT);
+    let out = scratch("out_holds_a_single_module/out.rs");
+    let output = run(&["rust", "--out", out.to_str().unwrap()], &[&vault, &loan]);
+    rejected(
+        &output,
+        "--out can only hold a single module in a single format, pass --out-dir to write several",
+    );
+    assert!(!out.exists());
+}
+
+#[test]
+fn out_holds_a_single_format() {
+    let vault = input("out_holds_a_single_format/vault.ts", VAULT);
+    let out = scratch("out_holds_a_single_format/out.rs");
+    let args = [
+        "generate",
+        "-f",
+        "rust,proto",
+        "--out",
+        out.to_str().unwrap(),
+    ];
+    let output = run(&args, &[&vault]);
+    rejected(
+        &output,
+        "--out can only hold a single module in a single format, pass --out-dir to write several",
+    );
+    assert!(!out.exists());
+}
+
+#[test]
+fn stdout_holds_a_single_module() {
+    let vault = input("stdout_holds_a_single_module/vault.ts", VAULT);
+    let loan = input("stdout_holds_a_single_module/loan.ts", VAULT);
+    let output = run(&["rust"], &[&vault, &loan]);
+    rejected(
+        &output,
+        "stdout can only hold a single module in a single format, pass --out-dir to write several",
+    );
+}
+
+#[test]
+fn out_dir_holds_several_modules() {
+    let vault = input("out_dir_holds_several_modules/vault.ts", VAULT);
+    let loan = input("out_dir_holds_several_modules/loan.ts", VAULT);
+    let dir = scratch("out_dir_holds_several_modules/out");
+    // Left by a previous run
+    let _ = std::fs::remove_dir_all(&dir);
+    let args = ["generate", "-f", "rust,proto", "-d", dir.to_str().unwrap()];
+    let output = run(&args, &[&vault, &loan]);
+    assert!(output.status.success());
+    for file in ["vault.rs", "vault.proto", "loan.rs", "loan.proto"] {
+        assert!(dir.join(file).exists(), "{} wasn't written", file);
+    }
+}
+
+#[test]
+fn out_dir_rejects_duplicate_files() {
+    let a = input("out_dir_rejects_duplicate_files/a/vault.ts", VAULT);
+    let b = input("out_dir_rejects_duplicate_files/b/vault.ts", VAULT);
+    let dir = scratch("out_dir_rejects_duplicate_files/out");
+    let output = run(&["rust", "-d", dir.to_str().unwrap()], &[&a, &b]);
+    rejected(
+        &output,
+        &format!(
+            "{} and {} would both be written to {}, rename one of them",
+            a.display(),
+            b.display(),
+            dir.join("vault.rs").display()
+        ),
+    );
+    // Nothing is written, not even the first module
+    assert!(!dir.exists());
+}
+
+#[test]
+fn missing_inputs() {
+    rejected(
+        &run(&["rust"], &[]),
+        "no input, pass TypeScript files as arguments or with --input",
+    );
+    let missing = scratch("missing_inputs/missing.ts");
+    let output = run(&["rust"], &[&missing]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with(&format!("error: failed to read {}: ", missing.display())));
+}
+
+#[test]
+fn unknown_format() {
+    let vault = input("unknown_format/vault.ts", VAULT);
+    let output = run(&["generate", "-f", "yaml"], &[&vault]);
+    // Usage errors are reported by clap, with its own exit status
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("error: invalid value 'yaml' for '--format <FORMATS>'"));
+}